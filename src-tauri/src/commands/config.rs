@@ -1,5 +1,6 @@
 use crate::models::AppConfig;
 use crate::state::AppState;
+use crate::util::lastfm::LastFmClient;
 use tauri::{AppHandle, Manager};
 
 #[tauri::command]
@@ -20,6 +21,18 @@ pub async fn save_app_config(app: AppHandle, config: AppConfig) -> Result<(), St
     config.save(&app)?;
 
     if let Some(state) = app.try_state::<AppState>() {
+        state.queue.set_hide_explicit(config.hide_explicit);
+        state.queue.set_end_of_queue(config.end_of_queue);
+        state
+            .queue
+            .set_default_provider(config.default_provider_id.clone());
+        state.queue.set_source_order(config.ordered_source_ids());
+        state
+            .queue
+            .set_auto_skip_threshold(config.auto_skip_threshold);
+        state.queue.set_max_queue_size(config.max_queue_size);
+        *state.lastfm.lock().await = LastFmClient::from_config(&config);
+
         if let Some(discord_config) = &config.discord_rpc {
             let mut discord = state.discord.lock().await;
             discord.update_config(discord_config.clone());
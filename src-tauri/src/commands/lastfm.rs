@@ -53,5 +53,18 @@ pub async fn finish_lastfm_login(
     let new_client = LastFmClient::new(Some(session.name), Some(session.key));
     *state_lfm = Some(new_client);
 
+    Ok(())
+}
+
+/// Rebuilds the shared client from the saved config, so turning Last.fm on or
+/// off reaches the scrobbler and Discord artwork lookups without a restart.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_lastfm_client(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let config = AppConfig::load(&app)?;
+    *state.lastfm.lock().await = LastFmClient::from_config(&config);
     Ok(())
 }
\ No newline at end of file
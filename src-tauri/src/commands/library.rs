@@ -1,13 +1,26 @@
-use crate::models::config::SourceConfig;
+use crate::models::config::{CoverArtSize, SourceConfig};
 use crate::models::entities::{
-    Album, Artist, Genre, LibraryStats, Playlist, Track, UnifiedSearchResult,
+    sort_album_tracks, AdjacentAlbums, Album, AlbumArtwork, AlbumDisc, Artist, ArtistFeature,
+    ArtistMergeResult, ConnectionTestResult, CoverCleanupResult, Genre, LibraryChangeKind,
+    LibraryChanged, LibraryStats, ListeningReport, Lyrics, LyricsSource, NowPlayingEntry, Playlist,
+    PodcastChannel, ProviderCapabilities, ProviderScanResult, SearchType, SourceKind,
+    SourceSearchResult, SourceStatus, SourceTracks, Track, TrackSort, UnifiedSearchResult,
 };
 use crate::providers::local::LocalProvider;
-use crate::providers::subsonic::SubsonicProvider;
+use crate::providers::subsonic::{SubsonicOptions, SubsonicProvider};
 use crate::state::AppState;
 use crate::traits::LibraryProvider;
 use rand::seq::SliceRandom;
 use tauri::{AppHandle, State};
+use tauri_specta::Event;
+
+fn emit_library_changed(app: &AppHandle, provider_id: &str, kind: LibraryChangeKind) {
+    let _ = LibraryChanged {
+        provider_id: provider_id.to_string(),
+        kind,
+    }
+    .emit(app);
+}
 
 #[tauri::command]
 #[specta::specta]
@@ -28,9 +41,15 @@ pub async fn add_source(
                 .join(crate::APP_IDENTIFIER);
             let db_path = app_data_dir.join(format!("library_{}.db", id));
 
-            let provider = LocalProvider::new(id.clone(), &db_path, &app_data_dir, config.clone())
-                .await
-                .map_err(|e| e.to_string())?;
+            let provider = LocalProvider::new(
+                id.clone(),
+                &db_path,
+                &app_data_dir,
+                config.clone(),
+                app.clone(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
 
             provider.add_root(&path).await?;
             provider.scan().await?;
@@ -47,10 +66,24 @@ pub async fn add_source(
             username,
             token,
             salt,
+            max_concurrent_requests,
+            request_timeout_secs,
             ..
         } => {
-            let provider = SubsonicProvider::new(id.clone(), name, url, username, token, salt)
-                .map_err(|e| e.to_string())?;
+            let provider = SubsonicProvider::new(
+                id.clone(),
+                name,
+                url,
+                username,
+                token,
+                salt,
+                SubsonicOptions {
+                    max_concurrent_requests,
+                    request_timeout_secs,
+                    cover_art_size: config.cover_art_size,
+                },
+            )
+            .map_err(|e| e.to_string())?;
 
             provider.scan().await?;
 
@@ -64,6 +97,88 @@ pub async fn add_source(
     Ok(())
 }
 
+/// Every configured source with its loaded state. Enabled sources are health
+/// checked in parallel.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_sources(
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> Result<Vec<SourceStatus>, String> {
+    let config = crate::models::AppConfig::load(&app)?;
+    let checks = config.sources.into_iter().map(|source| {
+        let (id, name, kind, enabled) = match source {
+            SourceConfig::Local {
+                id, name, enabled, ..
+            } => (id, name, SourceKind::Local, enabled),
+            SourceConfig::Subsonic {
+                id, name, enabled, ..
+            } => (id, name, SourceKind::Subsonic, enabled),
+        };
+        let queue = state.queue.clone();
+        async move {
+            let online = match queue.get_provider(&id).await {
+                Some(provider) if enabled => provider.is_online().await,
+                _ => false,
+            };
+            SourceStatus {
+                id,
+                name,
+                kind,
+                enabled,
+                online,
+            }
+        }
+    });
+    Ok(futures::future::join_all(checks).await)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn test_source_connection(source: SourceConfig) -> Result<ConnectionTestResult, String> {
+    match source {
+        SourceConfig::Local { path, .. } => {
+            let ok = std::path::Path::new(&path).is_dir();
+            Ok(ConnectionTestResult {
+                ok,
+                server_version: None,
+                message: if ok {
+                    "Folder found".to_string()
+                } else {
+                    format!("Folder not found: {}", path)
+                },
+            })
+        }
+        SourceConfig::Subsonic {
+            id,
+            name,
+            url,
+            username,
+            token,
+            salt,
+            max_concurrent_requests,
+            request_timeout_secs,
+            ..
+        } => {
+            let provider = SubsonicProvider::new(
+                id,
+                name,
+                url,
+                username,
+                token,
+                salt,
+                SubsonicOptions {
+                    max_concurrent_requests,
+                    request_timeout_secs,
+                    cover_art_size: CoverArtSize::default(),
+                },
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(provider.test_connection().await)
+        }
+    }
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn delete_source(
@@ -135,10 +250,15 @@ pub async fn toggle_source(
                         .join(crate::APP_IDENTIFIER);
                     let db_path = app_data_dir.join(format!("library_{}.db", id));
 
-                    let provider =
-                        LocalProvider::new(id.clone(), &db_path, &app_data_dir, config.clone())
-                            .await
-                            .map_err(|e| e.to_string())?;
+                    let provider = LocalProvider::new(
+                        id.clone(),
+                        &db_path,
+                        &app_data_dir,
+                        config.clone(),
+                        app.clone(),
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
 
                     provider.add_root(&path).await?;
                     state
@@ -153,11 +273,24 @@ pub async fn toggle_source(
                     username,
                     token,
                     salt,
+                    max_concurrent_requests,
+                    request_timeout_secs,
                     ..
                 } => {
-                    let provider =
-                        SubsonicProvider::new(id.clone(), name, url, username, token, salt)
-                            .map_err(|e| e.to_string())?;
+                    let provider = SubsonicProvider::new(
+                        id.clone(),
+                        name,
+                        url,
+                        username,
+                        token,
+                        salt,
+                        SubsonicOptions {
+                            max_concurrent_requests,
+                            request_timeout_secs,
+                            cover_art_size: config.cover_art_size,
+                        },
+                    )
+                    .map_err(|e| e.to_string())?;
 
                     state
                         .queue
@@ -184,6 +317,28 @@ pub async fn scan_libraries(state: State<'_, AppState>) -> Result<(), String> {
     Ok(())
 }
 
+/// Scans every provider one after another and reports how each one went.
+/// Local scans are CPU-bound, so running them concurrently wouldn't help.
+#[tauri::command]
+#[specta::specta]
+pub async fn scan_all(state: State<'_, AppState>) -> Result<Vec<ProviderScanResult>, String> {
+    let providers = state.queue.get_providers().await;
+    let mut results = Vec::with_capacity(providers.len());
+    for provider in providers.values() {
+        let res = provider.scan().await;
+        if let Err(e) = &res {
+            log::error!("Scan failed for provider {}: {}", provider.id(), e);
+        }
+        results.push(ProviderScanResult {
+            provider_id: provider.id().to_string(),
+            provider_name: provider.name().to_string(),
+            success: res.is_ok(),
+            error: res.err(),
+        });
+    }
+    Ok(results)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn scan_library(state: State<'_, AppState>, provider_id: String) -> Result<(), String> {
@@ -195,6 +350,17 @@ pub async fn scan_library(state: State<'_, AppState>, provider_id: String) -> Re
     provider.scan().await
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn cancel_scan(state: State<'_, AppState>, provider_id: String) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.cancel_scan().await
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn add_library_root(state: State<'_, AppState>, path: String) -> Result<(), String> {
@@ -226,10 +392,26 @@ pub async fn get_playlists(
     provider.get_playlists().await
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn get_playlist(
+    state: State<'_, AppState>,
+    provider_id: String,
+    playlist_id: String,
+) -> Result<Playlist, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.get_playlist(&playlist_id).await
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn create_playlist(
     state: State<'_, AppState>,
+    app: AppHandle,
     provider_id: String,
     name: String,
 ) -> Result<Playlist, String> {
@@ -238,28 +420,74 @@ pub async fn create_playlist(
         .get_provider(&provider_id)
         .await
         .ok_or("Provider not found".to_string())?;
-    provider.create_playlist(&name).await
+    let playlist = provider.create_playlist(&name).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::PlaylistChanged);
+    Ok(playlist)
 }
 
 #[tauri::command]
 #[specta::specta]
 pub async fn delete_playlist(
     state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    playlist_id: String,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.delete_playlist(&playlist_id).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::PlaylistChanged);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn rename_playlist(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    playlist_id: String,
+    new_name: String,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.rename_playlist(&playlist_id, &new_name).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::PlaylistChanged);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_playlist_cover(
+    state: State<'_, AppState>,
+    app: AppHandle,
     provider_id: String,
     playlist_id: String,
+    image_path: String,
 ) -> Result<(), String> {
     let provider = state
         .queue
         .get_provider(&provider_id)
         .await
         .ok_or("Provider not found".to_string())?;
-    provider.delete_playlist(&playlist_id).await
+    provider
+        .set_playlist_cover(&playlist_id, &image_path)
+        .await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::PlaylistChanged);
+    Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
 pub async fn add_to_playlist(
     state: State<'_, AppState>,
+    app: AppHandle,
     provider_id: String,
     playlist_id: String,
     track_id: String,
@@ -269,23 +497,55 @@ pub async fn add_to_playlist(
         .get_provider(&provider_id)
         .await
         .ok_or("Provider not found".to_string())?;
-    provider.add_to_playlist(&playlist_id, &track_id).await
+    provider.add_to_playlist(&playlist_id, &track_id).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::PlaylistChanged);
+    Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
 pub async fn remove_from_playlist(
     state: State<'_, AppState>,
+    app: AppHandle,
     provider_id: String,
     playlist_id: String,
     track_id: String,
+    position: Option<u32>,
 ) -> Result<(), String> {
     let provider = state
         .queue
         .get_provider(&provider_id)
         .await
         .ok_or("Provider not found".to_string())?;
-    provider.remove_from_playlist(&playlist_id, &track_id).await
+    // With the entry's `playlist_position`, only that occurrence is removed.
+    match position {
+        Some(position) => {
+            provider
+                .remove_from_playlist_at(&playlist_id, position)
+                .await?
+        }
+        None => {
+            provider
+                .remove_from_playlist(&playlist_id, &track_id)
+                .await?
+        }
+    }
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::PlaylistChanged);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_provider_capabilities(
+    state: State<'_, AppState>,
+    provider_id: String,
+) -> Result<ProviderCapabilities, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    Ok(provider.capabilities())
 }
 
 #[tauri::command]
@@ -300,7 +560,54 @@ pub async fn get_playlist_tracks(
         .get_provider(&provider_id)
         .await
         .ok_or("Provider not found".to_string())?;
-    provider.get_playlist_tracks(&playlist_id).await
+    let mut tracks = provider.get_playlist_tracks(&playlist_id).await?;
+    for (position, track) in tracks.iter_mut().enumerate() {
+        track.playlist_position = Some(position as u32);
+    }
+    Ok(tracks)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_podcasts(
+    state: State<'_, AppState>,
+    provider_id: String,
+) -> Result<Vec<PodcastChannel>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.get_podcasts().await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_podcast_episodes(
+    state: State<'_, AppState>,
+    provider_id: String,
+    channel_id: String,
+) -> Result<Vec<Track>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.get_podcast_episodes(&channel_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_server_now_playing(
+    state: State<'_, AppState>,
+    provider_id: String,
+) -> Result<Vec<NowPlayingEntry>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.get_server_now_playing().await
 }
 
 #[tauri::command]
@@ -309,9 +616,9 @@ pub async fn get_recent_albums(
     state: State<'_, AppState>,
     limit: u32,
 ) -> Result<Vec<Album>, String> {
-    let providers = state.queue.get_providers().await;
+    let providers = state.queue.ordered_providers().await;
     let mut all_albums = Vec::new();
-    for provider in providers.values() {
+    for provider in providers.iter() {
         if let Ok(mut albums) = provider.get_recent_albums(limit).await {
             all_albums.append(&mut albums);
         }
@@ -389,7 +696,9 @@ pub async fn get_library_stats(state: State<'_, AppState>) -> Result<LibraryStat
             total_stats.album_count += stats.album_count;
             total_stats.track_count += stats.track_count;
             total_stats.artist_count += stats.artist_count;
-            total_stats.total_duration += stats.total_duration;
+            total_stats.total_duration = total_stats
+                .total_duration
+                .saturating_add(stats.total_duration);
         }
     }
     Ok(total_stats)
@@ -401,13 +710,16 @@ pub async fn get_genre_tracks(
     state: State<'_, AppState>,
     genre_name: String,
 ) -> Result<Vec<Track>, String> {
-    let providers = state.queue.get_providers().await;
+    let providers = state.queue.ordered_providers().await;
     let mut all_tracks = Vec::new();
-    for provider in providers.values() {
+    for provider in providers.iter() {
         if let Ok(mut tracks) = provider.get_genre_tracks(&genre_name).await {
             all_tracks.append(&mut tracks);
         }
     }
+    if state.queue.hides_explicit() {
+        all_tracks.retain(|t| !t.explicit);
+    }
     all_tracks.sort_by(|a, b| b.play_count.cmp(&a.play_count));
     Ok(all_tracks)
 }
@@ -415,101 +727,793 @@ pub async fn get_genre_tracks(
 #[tauri::command]
 #[specta::specta]
 pub async fn get_favorites(state: State<'_, AppState>) -> Result<Vec<Track>, String> {
-    let providers = state.queue.get_providers().await;
-    let mut all_tracks = Vec::new();
-    for provider in providers.values() {
-        if let Ok(mut tracks) = provider.get_favorites().await {
-            all_tracks.append(&mut tracks);
+    let groups = get_favorites_by_source(state).await?;
+    Ok(groups.into_iter().flat_map(|g| g.tracks).collect())
+}
+
+/// Favorites of each provider, in the configured source order.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_favorites_by_source(
+    state: State<'_, AppState>,
+) -> Result<Vec<SourceTracks>, String> {
+    let providers = state.queue.ordered_providers().await;
+    let mut groups = Vec::new();
+    for provider in providers.iter() {
+        if let Ok(tracks) = provider.get_favorites().await {
+            groups.push(SourceTracks {
+                provider_id: provider.id().to_string(),
+                provider_name: provider.name().to_string(),
+                tracks,
+            });
         }
     }
-    Ok(all_tracks)
+    Ok(groups)
 }
 
 #[tauri::command]
 #[specta::specta]
 pub async fn set_favorite(
     state: State<'_, AppState>,
+    app: AppHandle,
     track_id: String,
     liked: bool,
 ) -> Result<(), String> {
     let providers = state.queue.get_providers().await;
     for provider in providers.values() {
-        let _ = provider.set_track_liked(&track_id, liked).await;
+        if provider.set_track_liked(&track_id, liked).await.is_ok() {
+            emit_library_changed(&app, provider.id(), LibraryChangeKind::FavoritesChanged);
+        }
     }
     Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn search(
+pub async fn set_track_explicit(
     state: State<'_, AppState>,
-    query: String,
-) -> Result<UnifiedSearchResult, String> {
-    let providers = state.queue.get_providers().await;
-    let mut result = UnifiedSearchResult::default();
+    app: AppHandle,
+    provider_id: String,
+    track_id: String,
+    explicit: bool,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.set_track_explicit(&track_id, explicit).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::TrackEdited);
+    Ok(())
+}
 
-    for provider in providers.values() {
-        if let Ok(res) = provider.search(&query).await {
-            result.tracks.extend(res.tracks);
-            result.albums.extend(res.albums);
-            result.artists.extend(res.artists);
+#[tauri::command]
+#[specta::specta]
+pub async fn set_track_rating(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    track_id: String,
+    rating: u8,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.set_track_rating(&track_id, rating.min(5)).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::TrackEdited);
+    Ok(())
+}
+
+async fn store_track_gain(
+    state: &AppState,
+    app: &AppHandle,
+    provider_id: &str,
+    track_id: &str,
+    gain_db: Option<f32>,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.set_track_gain(track_id, gain_db).await?;
+    if let Ok(track) = provider.get_track(track_id).await {
+        state.queue.update_track(&track).await;
+    }
+    if let Some(current) = state.queue.current_track().await {
+        if current.id == track_id && current.provider_id.as_deref() == Some(provider_id) {
+            state
+                .queue
+                .player
+                .set_track_gain(gain_db, current.peak)
+                .await?;
         }
     }
-    Ok(result)
+    emit_library_changed(app, provider_id, LibraryChangeKind::TrackEdited);
+    Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn get_artist(state: State<'_, AppState>, artist_id: String) -> Result<Artist, String> {
-    let providers = state.queue.get_providers().await;
-    for provider in providers.values() {
-        if let Ok(artist) = provider.get_artist(&artist_id).await {
-            return Ok(artist);
-        }
+pub async fn set_track_gain(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    track_id: String,
+    gain_db: f32,
+) -> Result<(), String> {
+    if !gain_db.is_finite() {
+        return Err("Invalid gain".to_string());
     }
-    Err("Artist not found".to_string())
+    let gain_db = gain_db.clamp(-24.0, 24.0);
+    store_track_gain(&state, &app, &provider_id, &track_id, Some(gain_db)).await
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn get_album(state: State<'_, AppState>, album_id: String) -> Result<Album, String> {
-    let providers = state.queue.get_providers().await;
-    for provider in providers.values() {
-        if let Ok(album) = provider.get_album(&album_id).await {
-            return Ok(album);
-        }
-    }
-    Err("Album not found".to_string())
+pub async fn clear_track_gain(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    track_id: String,
+) -> Result<(), String> {
+    store_track_gain(&state, &app, &provider_id, &track_id, None).await
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn get_artist_albums(
+pub async fn get_track_gain(
     state: State<'_, AppState>,
-    artist_id: String,
-) -> Result<Vec<Album>, String> {
-    let providers = state.queue.get_providers().await;
-    let mut all_albums = Vec::new();
-    for provider in providers.values() {
-        if let Ok(mut albums) = provider.get_artist_albums(&artist_id).await {
-            all_albums.append(&mut albums);
-        }
-    }
-    Ok(all_albums)
+    provider_id: String,
+    track_id: String,
+) -> Result<Option<f32>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    Ok(provider.get_track(&track_id).await?.gain_db)
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn get_album_tracks(
+pub async fn get_tracks_by_rating(
     state: State<'_, AppState>,
-    album_id: String,
+    min_rating: u8,
 ) -> Result<Vec<Track>, String> {
-    let providers = state.queue.get_providers().await;
+    let providers = state.queue.ordered_providers().await;
     let mut all_tracks = Vec::new();
-    for provider in providers.values() {
-        if let Ok(mut tracks) = provider.get_album_tracks(&album_id).await {
+    for provider in providers.iter() {
+        if let Ok(mut tracks) = provider.get_tracks_by_rating(min_rating.min(5)).await {
             all_tracks.append(&mut tracks);
         }
     }
+    if state.queue.hides_explicit() {
+        all_tracks.retain(|t| !t.explicit);
+    }
     Ok(all_tracks)
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_tracks_by_format(
+    state: State<'_, AppState>,
+    codec: Option<String>,
+    min_bitrate: Option<u32>,
+) -> Result<Vec<Track>, String> {
+    let providers = state.queue.ordered_providers().await;
+    let mut all_tracks = Vec::new();
+    for provider in providers.iter() {
+        if let Ok(mut tracks) = provider
+            .get_tracks_by_format(codec.as_deref(), min_bitrate)
+            .await
+        {
+            all_tracks.append(&mut tracks);
+        }
+    }
+    if state.queue.hides_explicit() {
+        all_tracks.retain(|t| !t.explicit);
+    }
+    Ok(all_tracks)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_lossless_tracks(state: State<'_, AppState>) -> Result<Vec<Track>, String> {
+    let providers = state.queue.ordered_providers().await;
+    let mut all_tracks = Vec::new();
+    for provider in providers.iter() {
+        if let Ok(mut tracks) = provider.get_lossless_tracks().await {
+            all_tracks.append(&mut tracks);
+        }
+    }
+    if state.queue.hides_explicit() {
+        all_tracks.retain(|t| !t.explicit);
+    }
+    Ok(all_tracks)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn is_favorite(
+    state: State<'_, AppState>,
+    provider_id: String,
+    track_id: String,
+) -> Result<bool, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.is_favorite(&track_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn search(
+    state: State<'_, AppState>,
+    query: String,
+    types: Option<Vec<SearchType>>,
+    min_length: Option<u32>,
+) -> Result<UnifiedSearchResult, String> {
+    let groups = search_by_source(state, query, types, min_length).await?;
+    let mut result = UnifiedSearchResult::default();
+    for group in groups {
+        result.tracks.extend(group.result.tracks);
+        result.albums.extend(group.result.albums);
+        result.artists.extend(group.result.artists);
+    }
+    Ok(result)
+}
+
+/// Like `search`, but keeps each provider's results apart, in the configured
+/// source order. Providers with no matches are left out.
+#[tauri::command]
+#[specta::specta]
+pub async fn search_by_source(
+    state: State<'_, AppState>,
+    query: String,
+    types: Option<Vec<SearchType>>,
+    min_length: Option<u32>,
+) -> Result<Vec<SourceSearchResult>, String> {
+    // Blank or too-short queries return nothing rather than hitting every provider.
+    let query = query.trim();
+    if query.is_empty() || query.chars().count() < min_length.unwrap_or(1) as usize {
+        return Ok(Vec::new());
+    }
+    let types = types.unwrap_or_else(|| SearchType::ALL.to_vec());
+    let providers = state.queue.ordered_providers().await;
+    let hide_explicit = state.queue.hides_explicit();
+    let mut groups = Vec::new();
+
+    for provider in providers.iter() {
+        if let Ok(mut result) = provider.search(query, &types).await {
+            if hide_explicit {
+                result.tracks.retain(|t| !t.explicit);
+            }
+            if result.tracks.is_empty() && result.albums.is_empty() && result.artists.is_empty() {
+                continue;
+            }
+            groups.push(SourceSearchResult {
+                provider_id: provider.id().to_string(),
+                provider_name: provider.name().to_string(),
+                result,
+            });
+        }
+    }
+    Ok(groups)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_tracks(
+    state: State<'_, AppState>,
+    track_ids: Vec<String>,
+    provider_id: Option<String>,
+) -> Result<Vec<Track>, String> {
+    Ok(state
+        .queue
+        .get_tracks(&track_ids, provider_id.as_deref())
+        .await)
+}
+
+/// Refetches a track from its provider, bypassing caches, and updates any
+/// queued copy so the now-playing view shows complete metadata.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_track(
+    state: State<'_, AppState>,
+    provider_id: String,
+    track_id: String,
+) -> Result<Track, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let track = provider.refresh_track(&track_id).await?;
+    state.queue.update_track(&track).await;
+    Ok(track)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_artist(state: State<'_, AppState>, artist_id: String) -> Result<Artist, String> {
+    let providers = state.queue.ordered_providers().await;
+    for provider in providers.iter() {
+        if let Ok(artist) = provider.get_artist(&artist_id).await {
+            return Ok(artist);
+        }
+    }
+    Err("Artist not found".to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_album(state: State<'_, AppState>, album_id: String) -> Result<Album, String> {
+    let providers = state.queue.ordered_providers().await;
+    for provider in providers.iter() {
+        if let Ok(album) = provider.get_album(&album_id).await {
+            return Ok(album);
+        }
+    }
+    Err("Album not found".to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_artist_albums(
+    state: State<'_, AppState>,
+    artist_id: String,
+) -> Result<Vec<Album>, String> {
+    let providers = state.queue.ordered_providers().await;
+    let mut all_albums = Vec::new();
+    for provider in providers.iter() {
+        if let Ok(mut albums) = provider.get_artist_albums(&artist_id).await {
+            all_albums.append(&mut albums);
+        }
+    }
+    Ok(all_albums)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_adjacent_albums(
+    state: State<'_, AppState>,
+    provider_id: String,
+    artist_id: String,
+    current_album_id: String,
+) -> Result<AdjacentAlbums, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider
+        .get_adjacent_albums(&artist_id, &current_album_id)
+        .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_artist_tracks(
+    state: State<'_, AppState>,
+    artist_id: String,
+    sort: Option<TrackSort>,
+    limit: Option<u32>,
+) -> Result<Vec<Track>, String> {
+    let sort = sort.unwrap_or_default();
+    let providers = state.queue.ordered_providers().await;
+    let mut all_tracks = Vec::new();
+    for provider in providers.iter() {
+        if let Ok(mut tracks) = provider.get_artist_tracks(&artist_id, sort, limit).await {
+            all_tracks.append(&mut tracks);
+        }
+    }
+    if state.queue.hides_explicit() {
+        all_tracks.retain(|t| !t.explicit);
+    }
+    sort.sort(&mut all_tracks);
+    if let Some(limit) = limit {
+        all_tracks.truncate(limit as usize);
+    }
+    Ok(all_tracks)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_artist_related(
+    state: State<'_, AppState>,
+    artist_id: String,
+) -> Result<Vec<Artist>, String> {
+    let providers = state.queue.ordered_providers().await;
+    for provider in providers.iter() {
+        if let Ok(artists) = provider.get_artist_related(&artist_id).await {
+            return Ok(artists);
+        }
+    }
+    Err("Not supported".to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_artist_features(
+    state: State<'_, AppState>,
+    artist_id: String,
+) -> Result<Vec<ArtistFeature>, String> {
+    let providers = state.queue.ordered_providers().await;
+    let mut all_features = Vec::new();
+    for provider in providers.iter() {
+        if let Ok(mut features) = provider.get_artist_features(&artist_id).await {
+            all_features.append(&mut features);
+        }
+    }
+    Ok(all_features)
+}
+
+/// Runs cover maintenance on every provider that keeps covers on disk and
+/// sums up what was done.
+#[tauri::command]
+#[specta::specta]
+pub async fn cleanup_covers(state: State<'_, AppState>) -> Result<CoverCleanupResult, String> {
+    let providers = state.queue.get_providers().await;
+    let mut total = CoverCleanupResult::default();
+    for provider in providers.values() {
+        match provider.cleanup_covers().await {
+            Ok(res) => {
+                total.files_removed += res.files_removed;
+                total.bytes_reclaimed += res.bytes_reclaimed;
+                total.albums_relinked += res.albums_relinked;
+                total.albums_cleared += res.albums_cleared;
+            }
+            Err(e) if e != "Not supported" => {
+                log::error!("Cover cleanup failed for {}: {}", provider.id(), e);
+            }
+            Err(_) => {}
+        }
+    }
+    Ok(total)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn merge_artists(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    keep_id: String,
+    merge_id: String,
+) -> Result<ArtistMergeResult, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let result = provider.merge_artists(&keep_id, &merge_id).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::ArtistsChanged);
+    Ok(result)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_album_artwork(
+    state: State<'_, AppState>,
+    provider_id: String,
+    album_id: String,
+) -> Result<Vec<AlbumArtwork>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.get_album_artwork(&album_id).await
+}
+
+/// Returns a few dominant colors of the album cover as `#rrggbb`, most common first.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_cover_palette(
+    state: State<'_, AppState>,
+    provider_id: String,
+    album_id: String,
+) -> Result<Vec<String>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let data = provider.get_cover_image(&album_id).await?;
+
+    let hash = crate::util::palette::cover_hash(&data);
+    if let Some(palette) = state.palettes.get(&hash).await {
+        return Ok(palette);
+    }
+    let palette =
+        tokio::task::spawn_blocking(move || crate::util::palette::extract_palette(&data, 5))
+            .await
+            .map_err(|e| e.to_string())??;
+    state.palettes.insert(hash, palette.clone()).await;
+    Ok(palette)
+}
+
+/// Tries each configured lyrics source in turn and returns the first lyrics
+/// found, tagged with their source. Results are cached per track; misses are
+/// only cached when every source answered, so a network blip doesn't stick.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_lyrics(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    track_id: String,
+) -> Result<Option<Lyrics>, String> {
+    let key = format!("{}:{}", provider_id, track_id);
+    if let Some(cached) = state.lyrics.get(&key).await {
+        return Ok(cached);
+    }
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let config =
+        crate::models::AppConfig::load(&app).map_err(|e| format!("Config error: {}", e))?;
+
+    let mut found = None;
+    let mut failed = false;
+    for source in config.lyrics_sources {
+        let res = match source {
+            LyricsSource::Lrclib => match provider.get_track(&track_id).await {
+                Ok(track) => state
+                    .lrclib
+                    .get(
+                        &track.artist_name,
+                        &track.title,
+                        &track.album_title,
+                        track.duration_sec,
+                    )
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            },
+            _ => provider.get_lyrics(&track_id, source).await,
+        };
+        match res {
+            Ok(Some(lyrics)) => {
+                found = Some(lyrics);
+                break;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!(
+                    "Lyrics lookup via {:?} failed for {}: {}",
+                    source,
+                    track_id,
+                    e
+                );
+                failed = true;
+            }
+        }
+    }
+
+    if found.is_some() || !failed {
+        state.lyrics.insert(key, found.clone()).await;
+    }
+    Ok(found)
+}
+
+/// Raw tags of the track's file, for inspecting metadata the library doesn't
+/// keep (ReplayGain values, encoder, custom fields).
+#[tauri::command]
+#[specta::specta]
+pub async fn get_audio_tags(
+    state: State<'_, AppState>,
+    provider_id: String,
+    track_id: String,
+) -> Result<std::collections::BTreeMap<String, Vec<String>>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.get_audio_tags(&track_id).await
+}
+
+/// Removes tracks whose files were under `path_prefix`, e.g. after the folder
+/// was deleted outside the app. Returns how many tracks were removed.
+#[tauri::command]
+#[specta::specta]
+pub async fn remove_tracks_under_path(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    path_prefix: String,
+) -> Result<u32, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let removed = provider.remove_tracks_under_path(&path_prefix).await?;
+    if removed > 0 {
+        emit_library_changed(&app, &provider_id, LibraryChangeKind::TracksRemoved);
+    }
+    Ok(removed)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn merge_albums(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    keep_id: String,
+    merge_id: String,
+) -> Result<u32, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let moved = provider.merge_albums(&keep_id, &merge_id).await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::AlbumsChanged);
+    Ok(moved)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn split_album(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    provider_id: String,
+    album_id: String,
+    track_ids: Vec<String>,
+    new_title: String,
+) -> Result<Album, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let album = provider
+        .split_album(&album_id, &track_ids, &new_title)
+        .await?;
+    emit_library_changed(&app, &provider_id, LibraryChangeKind::AlbumsChanged);
+    Ok(album)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn prefetch_album(
+    state: State<'_, AppState>,
+    provider_id: String,
+    album_id: String,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    provider.prefetch_album(&album_id).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_album_tracks(
+    state: State<'_, AppState>,
+    album_id: String,
+) -> Result<Vec<Track>, String> {
+    let providers = state.queue.get_providers().await;
+    let mut all_tracks = Vec::new();
+    for provider in providers.values() {
+        if let Ok(mut tracks) = provider.get_album_tracks(&album_id).await {
+            all_tracks.append(&mut tracks);
+        }
+    }
+    if state.queue.hides_explicit() {
+        all_tracks.retain(|t| !t.explicit);
+    }
+    sort_album_tracks(&mut all_tracks);
+    Ok(all_tracks)
+}
+
+/// An album's tracks grouped by disc, in disc order. Tracks without a disc
+/// number are treated as disc 1.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_album_discs(
+    state: State<'_, AppState>,
+    provider_id: String,
+    album_id: String,
+) -> Result<Vec<AlbumDisc>, String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let mut tracks = provider.get_album_tracks(&album_id).await?;
+    if state.queue.hides_explicit() {
+        tracks.retain(|t| !t.explicit);
+    }
+    sort_album_tracks(&mut tracks);
+
+    let mut discs: std::collections::BTreeMap<u32, Vec<Track>> = Default::default();
+    for track in tracks {
+        discs
+            .entry(track.disc_number.unwrap_or(1).max(1))
+            .or_default()
+            .push(track);
+    }
+    Ok(discs
+        .into_iter()
+        .map(|(disc_number, tracks)| AlbumDisc {
+            disc_number,
+            tracks,
+        })
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_recently_played(
+    state: State<'_, AppState>,
+    limit: u32,
+) -> Result<Vec<Track>, String> {
+    let entries = state
+        .history
+        .recent_tracks(limit)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut tracks = Vec::new();
+    for (provider_id, track_id) in entries {
+        if let Some(track) = state
+            .queue
+            .get_track(&track_id, provider_id.as_deref())
+            .await
+        {
+            tracks.push(track);
+        }
+    }
+    Ok(tracks)
+}
+
+/// Listening stats between `from` and `to`, with the `limit` most played
+/// artists, albums and tracks (10 by default).
+#[tauri::command]
+#[specta::specta]
+pub async fn get_listening_report(
+    state: State<'_, AppState>,
+    from: chrono::DateTime<chrono::Utc>,
+    to: chrono::DateTime<chrono::Utc>,
+    limit: Option<u32>,
+) -> Result<ListeningReport, String> {
+    state
+        .history
+        .report(from, to, limit.unwrap_or(10))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_recently_played_albums(
+    state: State<'_, AppState>,
+    limit: u32,
+) -> Result<Vec<Album>, String> {
+    let entries = state
+        .history
+        .recent_albums(limit)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut albums = Vec::new();
+    for (provider_id, album_id) in entries {
+        if let Some(provider) = state.queue.get_provider(&provider_id).await {
+            if let Ok(album) = provider.get_album(&album_id).await {
+                albums.push(album);
+            }
+        }
+    }
+    Ok(albums)
+}
@@ -1,23 +1,209 @@
-use crate::models::player::AudioDevice;
-use crate::models::{player::PlayerState, player::RepeatMode};
+use crate::models::entities::{sort_album_tracks, PlaybackFailure, SearchType, Track};
+use crate::models::player::{AudioDevice, CurrentContext, EngineInfo};
+use crate::models::{player::PlayerState, player::RepeatMode, AppConfig, AudioBackend};
 use crate::state::AppState;
-use tauri::State;
+use crate::util::lastfm::LastFmClient;
+use futures::StreamExt;
+use rand::seq::SliceRandom;
+use tauri::{AppHandle, State};
 
+/// Plays the track right away. It's slotted in after the current track so the
+/// rest of the queue survives; `replace_queue` clears the queue instead.
 #[tauri::command]
 #[specta::specta]
-pub async fn play_track(state: State<'_, AppState>, track_id: String) -> Result<(), String> {
+pub async fn play_track(
+    state: State<'_, AppState>,
+    track_id: String,
+    provider_id: Option<String>,
+    replace_queue: Option<bool>,
+) -> Result<(), String> {
     let track = state
         .queue
-        .get_track(&track_id)
+        .get_track(&track_id, provider_id.as_deref())
         .await
         .ok_or("Track not found in any provider".to_string())?;
-    state.queue.play_now(track).await
+    if replace_queue.unwrap_or(false) {
+        state.queue.play_now(track).await
+    } else {
+        state.queue.play_in_queue(track).await
+    }
+}
+
+/// Replaces the queue with the album in disc and track order and starts playing,
+/// from `start_track_id` when given.
+#[tauri::command]
+#[specta::specta]
+pub async fn play_album(
+    state: State<'_, AppState>,
+    provider_id: String,
+    album_id: String,
+    start_track_id: Option<String>,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let mut tracks = provider.get_album_tracks(&album_id).await?;
+    if state.queue.hides_explicit() {
+        tracks.retain(|t| !t.explicit);
+    }
+    if tracks.is_empty() {
+        return Err("Album has no tracks".to_string());
+    }
+    sort_album_tracks(&mut tracks);
+    let start = start_track_id
+        .and_then(|id| tracks.iter().position(|t| t.id == id))
+        .unwrap_or(0);
+    state.queue.set_queue(tracks, start, true).await
+}
+
+/// How many similar tracks to ask for when starting a track radio or instant mix.
+const RADIO_CANDIDATES: u32 = 50;
+
+/// Replaces the queue with the seed track followed by tracks Last.fm deems
+/// similar, matched against the provider's own catalogue by search. Candidates
+/// the provider doesn't have are skipped.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_track_radio(
+    state: State<'_, AppState>,
+    provider_id: String,
+    track_id: String,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let seed = provider.get_track(&track_id).await?;
+
+    let client = state
+        .lastfm
+        .lock()
+        .await
+        .clone()
+        .unwrap_or_else(|| LastFmClient::new(None, None));
+    let similar = client
+        .get_similar_tracks(&seed.artist_name, &seed.title, RADIO_CANDIDATES)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let matches: Vec<Option<Track>> = futures::stream::iter(similar)
+        .map(|candidate| {
+            let provider = provider.clone();
+            async move {
+                let query = format!("{} {}", candidate.artist.name, candidate.name);
+                let result = provider.search(&query, &[SearchType::Tracks]).await.ok()?;
+                result.tracks.into_iter().find(|t| {
+                    t.title.eq_ignore_ascii_case(&candidate.name)
+                        && t.artist_name.eq_ignore_ascii_case(&candidate.artist.name)
+                })
+            }
+        })
+        .buffered(4)
+        .collect()
+        .await;
+
+    let hide_explicit = state.queue.hides_explicit();
+    let mut seen = std::collections::HashSet::from([seed.id.clone()]);
+    let mut tracks = vec![seed];
+    for track in matches.into_iter().flatten() {
+        if hide_explicit && track.explicit {
+            continue;
+        }
+        if seen.insert(track.id.clone()) {
+            tracks.push(track);
+        }
+    }
+
+    state.queue.set_queue(tracks, 0, true).await
+}
+
+/// Replaces the queue with the seed track followed by the provider's own
+/// similar songs (Subsonic's instant mix) and starts playing.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_instant_mix(
+    state: State<'_, AppState>,
+    provider_id: String,
+    track_id: String,
+    count: Option<u32>,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let seed = provider.get_track(&track_id).await?;
+    let mut similar = provider
+        .get_similar_tracks(&track_id, count.unwrap_or(RADIO_CANDIDATES))
+        .await?;
+    similar.retain(|t| t.id != seed.id);
+    if state.queue.hides_explicit() {
+        similar.retain(|t| !t.explicit);
+    }
+
+    let mut tracks = vec![seed];
+    tracks.extend(similar);
+    state.queue.set_queue(tracks, 0, true).await
+}
+
+/// Replaces the queue with every track in `genre` and starts playing. With
+/// `shuffle` the order is randomized before `limit` is applied, so a limited
+/// session samples the whole genre rather than its first N tracks.
+#[tauri::command]
+#[specta::specta]
+pub async fn play_genre(
+    state: State<'_, AppState>,
+    provider_id: String,
+    genre: String,
+    shuffle: bool,
+    limit: Option<u32>,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let mut tracks = provider.get_genre_tracks(&genre).await?;
+    if state.queue.hides_explicit() {
+        tracks.retain(|t| !t.explicit);
+    }
+    if tracks.is_empty() {
+        return Err("No tracks in genre".to_string());
+    }
+    if shuffle {
+        tracks.shuffle(&mut rand::rng());
+    }
+    if let Some(limit) = limit {
+        tracks.truncate(limit as usize);
+    }
+    state.queue.set_queue(tracks, 0, true).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn preview_track(
+    state: State<'_, AppState>,
+    provider_id: String,
+    track_id: String,
+    start_sec: f64,
+    duration_sec: f64,
+) -> Result<(), String> {
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+    let stream = provider.resolve_stream(&track_id).await?;
+    state.queue.preview(stream, start_sec, duration_sec).await
 }
 
 #[tauri::command]
 #[specta::specta]
 pub async fn play(state: State<'_, AppState>) -> Result<(), String> {
-    state.queue.player.play().await
+    state.queue.play().await
 }
 
 #[tauri::command]
@@ -29,7 +215,14 @@ pub async fn pause(state: State<'_, AppState>) -> Result<(), String> {
 #[tauri::command]
 #[specta::specta]
 pub async fn stop(state: State<'_, AppState>) -> Result<(), String> {
-    state.queue.player.stop().await
+    state.queue.stop().await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_stop_after_current(state: State<'_, AppState>, enabled: bool) -> Result<(), String> {
+    state.queue.set_stop_after_current(enabled);
+    Ok(())
 }
 
 #[tauri::command]
@@ -56,6 +249,12 @@ pub async fn set_volume(state: State<'_, AppState>, volume: f32) -> Result<(), S
     state.queue.player.set_volume(volume).await
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn toggle_mute(state: State<'_, AppState>, muted: Option<bool>) -> Result<bool, String> {
+    state.queue.player.toggle_mute(muted).await
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn set_repeat(state: State<'_, AppState>, mode: RepeatMode) -> Result<(), String> {
@@ -75,11 +274,48 @@ pub async fn get_player_state(state: State<'_, AppState>) -> Result<PlayerState,
     Ok(state.queue.player.get_state().await)
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn get_current_context(
+    state: State<'_, AppState>,
+) -> Result<Option<CurrentContext>, String> {
+    let Some(track) = state.queue.current_track().await else {
+        return Ok(None);
+    };
+    let provider_id = track
+        .provider_id
+        .clone()
+        .ok_or("Track has no provider".to_string())?;
+    let provider = state
+        .queue
+        .get_provider(&provider_id)
+        .await
+        .ok_or("Provider not found".to_string())?;
+
+    let (album, artist) = futures::join!(
+        provider.get_album(&track.album_id),
+        provider.get_artist(&track.artist_id)
+    );
+
+    Ok(Some(CurrentContext {
+        track,
+        album: album.ok(),
+        artist: artist.ok(),
+        provider_id,
+    }))
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn get_audio_devices(state: State<'_, AppState>) -> Result<Vec<AudioDevice>, String> {
     state.queue.player.get_audio_devices().await
 }
+#[tauri::command]
+#[specta::specta]
+pub async fn get_engine_info(state: State<'_, AppState>) -> Result<EngineInfo, String> {
+    state.queue.player.get_engine_info().await
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn set_audio_device(
@@ -97,3 +333,77 @@ pub async fn toggle_exclusive_mode(
 ) -> Result<bool, String> {
     state.queue.player.toggle_exclusive_mode(exclusive).await
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_night_mode(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    state.queue.player.set_night_mode(enabled).await?;
+
+    let mut config = AppConfig::load(&app)?;
+    match &mut config.audio_engine {
+        AudioBackend::Mpv(mpv_opts) => mpv_opts.night_mode = enabled,
+    }
+    config.save(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn set_prevent_clipping(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), String> {
+    state.queue.player.set_prevent_clipping(enabled).await?;
+
+    let mut config = AppConfig::load(&app)?;
+    match &mut config.audio_engine {
+        AudioBackend::Mpv(mpv_opts) => mpv_opts.prevent_clipping = enabled,
+    }
+    config.save(&app)
+}
+
+/// Replaces the running audio engine with one built from `backend`, carrying
+/// over the current track, position and volume.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_audio_backend(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    backend: AudioBackend,
+) -> Result<(), String> {
+    let mut config = AppConfig::load(&app)?;
+    config.audio_engine = backend;
+
+    let engine = crate::create_audio_engine(&config).map_err(|e| e.to_string())?;
+    let previous = state.queue.player.get_state().await;
+    drop(state.engine.swap(engine).await);
+    state.queue.reload_current(&previous).await?;
+
+    config.save(&app)
+}
+
+/// Resolves the current track's stream again and reloads it at the current
+/// position, for streams that stopped working (e.g. an expired URL).
+#[tauri::command]
+#[specta::specta]
+pub async fn reload_current(state: State<'_, AppState>) -> Result<(), String> {
+    let previous = state.queue.player.get_state().await;
+    state.queue.reload_current(&previous).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_recent_errors(state: State<'_, AppState>) -> Result<Vec<PlaybackFailure>, String> {
+    Ok(state.queue.recent_failures())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn clear_recent_errors(state: State<'_, AppState>) -> Result<(), String> {
+    state.queue.clear_recent_failures();
+    Ok(())
+}
@@ -1,4 +1,7 @@
-use crate::models::player::Queue;
+use crate::models::{
+    player::{NavigationState, Queue},
+    Track,
+};
 use crate::state::AppState;
 use tauri::State;
 
@@ -10,10 +13,26 @@ pub async fn get_queue(state: State<'_, AppState>) -> Result<Queue, String> {
 
 #[tauri::command]
 #[specta::specta]
-pub async fn add_to_queue(state: State<'_, AppState>, track_id: String) -> Result<(), String> {
+pub async fn get_navigation_state(state: State<'_, AppState>) -> Result<NavigationState, String> {
+    Ok(state.queue.navigation_state().await)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn peek_next(state: State<'_, AppState>, count: u32) -> Result<Vec<Track>, String> {
+    Ok(state.queue.peek_next(count as usize).await)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn add_to_queue(
+    state: State<'_, AppState>,
+    track_id: String,
+    provider_id: Option<String>,
+) -> Result<(), String> {
     let track = state
         .queue
-        .get_track(&track_id)
+        .get_track(&track_id, provider_id.as_deref())
         .await
         .ok_or("Track not found in any provider".to_string())?;
     state.queue.add_track(track).await;
@@ -25,12 +44,13 @@ pub async fn add_to_queue(state: State<'_, AppState>, track_id: String) -> Resul
 pub async fn add_to_queue_multiple(
     state: State<'_, AppState>,
     track_ids: Vec<String>,
+    provider_id: Option<String>,
 ) -> Result<(), String> {
     let mut tracks = Vec::new();
     for track_id in track_ids {
         let track = state
             .queue
-            .get_track(&track_id)
+            .get_track(&track_id, provider_id.as_deref())
             .await
             .ok_or("One or more tracks not found in any provider".to_string())?;
         tracks.push(track);
@@ -41,16 +61,59 @@ pub async fn add_to_queue_multiple(
 
 #[tauri::command]
 #[specta::specta]
-pub async fn add_next(state: State<'_, AppState>, track_id: String) -> Result<(), String> {
+pub async fn set_queue(
+    state: State<'_, AppState>,
+    track_ids: Vec<String>,
+    start_index: u32,
+    autoplay: bool,
+    provider_id: Option<String>,
+) -> Result<(), String> {
+    let tracks = state
+        .queue
+        .get_tracks(&track_ids, provider_id.as_deref())
+        .await;
+    if tracks.len() != track_ids.len() {
+        return Err("One or more tracks not found in any provider".to_string());
+    }
+    state
+        .queue
+        .set_queue(tracks, start_index as usize, autoplay)
+        .await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn add_next(
+    state: State<'_, AppState>,
+    track_id: String,
+    provider_id: Option<String>,
+) -> Result<(), String> {
     let track = state
         .queue
-        .get_track(&track_id)
+        .get_track(&track_id, provider_id.as_deref())
         .await
         .ok_or("Track not found in any provider".to_string())?;
     state.queue.add_next(track).await;
     Ok(())
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn insert_in_queue(
+    state: State<'_, AppState>,
+    track_id: String,
+    index: u32,
+    provider_id: Option<String>,
+) -> Result<(), String> {
+    let track = state
+        .queue
+        .get_track(&track_id, provider_id.as_deref())
+        .await
+        .ok_or("Track not found in any provider".to_string())?;
+    state.queue.insert(track, index as usize).await;
+    Ok(())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn remove_from_queue(state: State<'_, AppState>, index: u32) -> Result<(), String> {
@@ -65,8 +128,39 @@ pub async fn clear_queue(state: State<'_, AppState>) -> Result<(), String> {
     Ok(())
 }
 
+#[tauri::command]
+#[specta::specta]
+pub async fn trim_played_from_queue(state: State<'_, AppState>) -> Result<(), String> {
+    state.queue.trim_played().await;
+    Ok(())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn play_from_queue(state: State<'_, AppState>, index: u32) -> Result<(), String> {
     state.queue.play_index(index as usize).await
 }
+
+#[tauri::command]
+#[specta::specta]
+pub async fn save_session(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    state.queue.save_session(&name).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_sessions(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    state.queue.list_sessions()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn load_session(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    state.queue.load_session(&name).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_session(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    state.queue.delete_session(&name).await
+}
@@ -27,15 +27,16 @@ pub mod util;
 
 use crate::models::{config::SourceConfig, AppConfig, AudioBackend};
 use crate::players::mpv::MpvPlayer;
+use crate::players::switchable::SwitchableEngine;
 use crate::providers::local::LocalProvider;
-use crate::providers::subsonic::SubsonicProvider;
+use crate::providers::subsonic::{SubsonicOptions, SubsonicProvider};
 use crate::queue::QueueManager;
 use crate::state::AppState;
 use crate::traits::{AudioEngine, LibraryProvider};
 use std::collections::HashMap;
 use tauri::Manager;
 
-fn create_audio_engine(config: &AppConfig) -> anyhow::Result<Box<dyn AudioEngine>> {
+pub(crate) fn create_audio_engine(config: &AppConfig) -> anyhow::Result<Box<dyn AudioEngine>> {
     match &config.audio_engine {
         AudioBackend::Mpv(mpv_opts) => Ok(Box::new(MpvPlayer::new(mpv_opts.clone())?)),
     }
@@ -47,49 +48,115 @@ pub async fn run() {
     let builder = tauri_specta::Builder::<tauri::Wry>::new()
         .commands(tauri_specta::collect_commands![
             commands::player::play_track,
+            commands::player::play_album,
+            commands::player::start_track_radio,
+            commands::player::start_instant_mix,
+            commands::player::play_genre,
+            commands::player::preview_track,
             commands::player::play,
             commands::player::pause,
             commands::player::stop,
+            commands::player::set_stop_after_current,
             commands::player::next,
             commands::player::prev,
             commands::player::seek,
             commands::player::set_volume,
+            commands::player::toggle_mute,
             commands::player::set_repeat,
             commands::player::toggle_shuffle,
             commands::player::get_player_state,
+            commands::player::get_current_context,
             commands::player::get_audio_devices,
             commands::player::set_audio_device,
             commands::player::toggle_exclusive_mode,
+            commands::player::set_night_mode,
+            commands::player::set_prevent_clipping,
+            commands::player::get_engine_info,
+            commands::player::set_audio_backend,
+            commands::player::reload_current,
+            commands::player::get_recent_errors,
+            commands::player::clear_recent_errors,
             commands::queue::get_queue,
+            commands::queue::peek_next,
+            commands::queue::get_navigation_state,
             commands::queue::add_to_queue,
             commands::queue::add_to_queue_multiple,
+            commands::queue::set_queue,
             commands::queue::add_next,
+            commands::queue::insert_in_queue,
             commands::queue::remove_from_queue,
             commands::queue::clear_queue,
+            commands::queue::trim_played_from_queue,
             commands::queue::play_from_queue,
+            commands::queue::save_session,
+            commands::queue::list_sessions,
+            commands::queue::load_session,
+            commands::queue::delete_session,
             commands::library::scan_libraries,
+            commands::library::scan_all,
             commands::library::scan_library,
+            commands::library::cancel_scan,
             commands::library::add_library_root,
             commands::library::get_playlists,
+            commands::library::get_playlist,
             commands::library::create_playlist,
             commands::library::delete_playlist,
+            commands::library::rename_playlist,
+            commands::library::set_playlist_cover,
             commands::library::add_to_playlist,
             commands::library::remove_from_playlist,
             commands::library::get_playlist_tracks,
+            commands::library::get_provider_capabilities,
+            commands::library::get_podcasts,
+            commands::library::get_podcast_episodes,
+            commands::library::get_server_now_playing,
             commands::library::get_recent_albums,
+            commands::library::get_recently_played,
+            commands::library::get_recently_played_albums,
+            commands::library::get_listening_report,
             commands::library::get_random_albums,
             commands::library::get_most_played_tracks,
             commands::library::get_genres,
             commands::library::get_genre_tracks,
             commands::library::get_library_stats,
             commands::library::get_favorites,
+            commands::library::get_favorites_by_source,
             commands::library::search,
+            commands::library::search_by_source,
+            commands::library::get_tracks,
+            commands::library::refresh_track,
             commands::library::get_artist,
             commands::library::get_album,
+            commands::library::get_album_artwork,
+            commands::library::get_cover_palette,
+            commands::library::get_lyrics,
+            commands::library::get_audio_tags,
+            commands::library::cleanup_covers,
             commands::library::get_artist_albums,
+            commands::library::get_adjacent_albums,
+            commands::library::get_artist_tracks,
+            commands::library::get_artist_related,
+            commands::library::get_artist_features,
+            commands::library::merge_artists,
+            commands::library::merge_albums,
+            commands::library::remove_tracks_under_path,
+            commands::library::split_album,
             commands::library::get_album_tracks,
+            commands::library::get_album_discs,
+            commands::library::prefetch_album,
             commands::library::set_favorite,
+            commands::library::is_favorite,
+            commands::library::set_track_explicit,
+            commands::library::set_track_rating,
+            commands::library::set_track_gain,
+            commands::library::clear_track_gain,
+            commands::library::get_track_gain,
+            commands::library::get_tracks_by_rating,
+            commands::library::get_tracks_by_format,
+            commands::library::get_lossless_tracks,
             commands::library::add_source,
+            commands::library::get_sources,
+            commands::library::test_source_connection,
             commands::library::delete_source,
             commands::library::toggle_source,
             commands::config::get_default_config,
@@ -97,15 +164,22 @@ pub async fn run() {
             commands::config::save_app_config,
             commands::lastfm::login_lastfm,
             commands::lastfm::finish_lastfm_login,
+            commands::lastfm::refresh_lastfm_client,
         ])
         .events(tauri_specta::collect_events![
-            crate::models::entities::PlayerEvent
+            crate::models::entities::PlayerEvent,
+            crate::models::entities::ScanRootFailed,
+            crate::models::entities::ScanCancelled,
+            crate::models::entities::LibraryChanged,
+            crate::models::entities::LibraryDatabaseReset,
+            crate::models::entities::TrackChanged
         ]);
 
     #[cfg(debug_assertions)]
     builder
         .export(
-            specta_typescript::Typescript::default(),
+            specta_typescript::Typescript::default()
+                .bigint(specta_typescript::BigIntExportBehavior::Number),
             "../src/lib/bindings.ts",
         )
         .expect("Failed to export typescript bindings");
@@ -143,8 +217,9 @@ pub async fn run() {
                 AppConfig::default()
             });
 
-            let player = create_audio_engine(&config)
+            let engine = create_audio_engine(&config)
                 .map_err(|e| -> Box<dyn std::error::Error> { e.into() })?;
+            let engine = SwitchableEngine::new(engine);
 
             let app_data_dir = app
                 .path()
@@ -152,26 +227,28 @@ pub async fn run() {
                 .expect("failed to get app data dir");
             let state_path = app_data_dir.join("playback_state.json");
 
-            let queue = QueueManager::new(player, providers, state_path);
+            let queue = QueueManager::new(Box::new(engine.clone()), providers, state_path);
+            queue.set_hide_explicit(config.hide_explicit);
+            queue.set_end_of_queue(config.end_of_queue);
+            queue.set_default_provider(config.default_provider_id.clone());
+            queue.set_source_order(config.ordered_source_ids());
+            queue.set_auto_skip_threshold(config.auto_skip_threshold);
+            queue.set_max_queue_size(config.max_queue_size);
 
-            let mut lastfm_client = None;
-            if let Some(lfm_config) = &config.lastfm_session {
-                if lfm_config.enabled {
-                    let client = crate::util::lastfm::LastFmClient::new(
-                        Some(lfm_config.username.clone()),
-                        Some(lfm_config.session_key.clone()),
-                    );
-                    lastfm_client = Some(client);
-                }
-            }
+            let lastfm_client = crate::util::lastfm::LastFmClient::from_config(&config);
 
             let discord_config = config.discord_rpc.clone().unwrap_or_default();
             let discord_rpc = crate::util::discord::DiscordRpc::new(discord_config);
 
+            let _ = std::fs::create_dir_all(&app_data_dir);
+            let history = crate::util::history::PlayHistory::new(&app_data_dir.join("history.db"));
+
             app.manage(AppState::new(
                 queue.clone(),
+                engine,
                 lastfm_client.clone(),
                 discord_rpc,
+                history,
             ));
 
             let handle = app.handle().clone();
@@ -194,6 +271,7 @@ pub async fn run() {
                                     &db_path,
                                     &data_dir,
                                     config.clone(),
+                                    handle.clone(),
                                 )
                                 .await
                                 {
@@ -211,6 +289,8 @@ pub async fn run() {
                             token,
                             salt,
                             enabled,
+                            max_concurrent_requests,
+                            request_timeout_secs,
                         } => {
                             if !enabled {
                                 continue;
@@ -222,6 +302,11 @@ pub async fn run() {
                                 username.clone(),
                                 token.clone(),
                                 salt.clone(),
+                                SubsonicOptions {
+                                    max_concurrent_requests: *max_concurrent_requests,
+                                    request_timeout_secs: *request_timeout_secs,
+                                    cover_art_size: config.cover_art_size,
+                                },
                             ) {
                                 queue.add_provider(Arc::new(provider)).await;
                             }
@@ -229,7 +314,7 @@ pub async fn run() {
                     }
                 }
 
-                queue.load_state().await;
+                queue.load_state(config.default_volume).await;
 
                 let state = handle.state::<AppState>();
 
@@ -268,8 +353,26 @@ pub async fn run() {
                     state.discord.clone(),
                     state.lastfm.clone(),
                 );
+                crate::util::history::start_history_service(
+                    state.queue.clone(),
+                    state.history.clone(),
+                );
 
                 use tauri_specta::Event;
+                let mut track_rx = queue.subscribe_track_changes();
+                let track_handle = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        match track_rx.recv().await {
+                            Ok(event) => {
+                                let _ = event.emit(&track_handle);
+                            }
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                        }
+                    }
+                });
+
                 let mut rx = queue.player.subscribe();
 
                 while let Ok(event) = rx.recv().await {
@@ -278,6 +381,20 @@ pub async fn run() {
             });
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<AppState>() {
+                    let queue = state.queue.clone();
+                    tokio::task::block_in_place(|| {
+                        tokio::runtime::Handle::current().block_on(async {
+                            if let Err(e) = queue.save().await {
+                                log::error!("Failed to save playback state on exit: {}", e);
+                            }
+                        })
+                    });
+                }
+            }
+        });
 }
@@ -1,3 +1,4 @@
+use crate::models::entities::LyricsSource;
 use serde::{Deserialize, Serialize};
 
 use specta::Type;
@@ -14,6 +15,77 @@ pub struct AppConfig {
     pub audio_engine: AudioBackend,
     pub lastfm_session: Option<LastFmSessionConfig>,
     pub discord_rpc: Option<DiscordRpcConfig>,
+    #[serde(default = "default_volume")]
+    pub default_volume: f32,
+    #[serde(default)]
+    pub hide_explicit: bool,
+    #[serde(default)]
+    pub cover_art_size: CoverArtSize,
+    #[serde(default)]
+    pub end_of_queue: EndOfQueueBehavior,
+    /// Shown in place of a missing artist tag. Only affects display; the
+    /// library keeps its own fixed placeholder internally.
+    #[serde(default = "default_unknown_artist_label")]
+    pub unknown_artist_label: String,
+    #[serde(default = "default_unknown_album_label")]
+    pub unknown_album_label: String,
+    /// Keep artist images that are already set instead of replacing them with
+    /// Last.fm's during scan enrichment.
+    #[serde(default = "default_true")]
+    pub prefer_local_art: bool,
+    /// Let Last.fm bios replace ones that are already set.
+    #[serde(default)]
+    pub overwrite_artist_bio: bool,
+    /// Source tried first when a command gets a track id without a provider.
+    #[serde(default)]
+    pub default_provider_id: Option<String>,
+    /// Tracks skipped at least this many times go to the end of the shuffle
+    /// order. `None` shuffles everything evenly.
+    #[serde(default)]
+    pub auto_skip_threshold: Option<u32>,
+    /// Cap on queued tracks. Appending past it drops already-played tracks from
+    /// the front. `None` lets the queue grow without limit.
+    #[serde(default)]
+    pub max_queue_size: Option<u32>,
+    /// Sort artists and albums by their sort name (the ARTISTSORT/ALBUMSORT tag,
+    /// or the name without a leading article) instead of the name as shown.
+    #[serde(default = "default_true")]
+    pub use_sort_names: bool,
+    /// Leading articles dropped for sorting, e.g. "The" so "The Beatles" sorts
+    /// under B. Entries ending in an apostrophe ("L'") match without a space.
+    #[serde(default = "default_sort_articles")]
+    pub sort_articles: Vec<String>,
+    /// Provider ids in the order views combining several sources (search,
+    /// favorites, ...) list their results. Unlisted sources follow in the order
+    /// they're configured.
+    #[serde(default)]
+    pub source_order: Vec<String>,
+    /// Where `get_lyrics` looks, in order. The first source with lyrics wins.
+    #[serde(default = "default_lyrics_sources")]
+    pub lyrics_sources: Vec<LyricsSource>,
+}
+
+/// What happens when the last track in the queue finishes and repeat is off.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum EndOfQueueBehavior {
+    #[default]
+    // Configs saved while stop-after-current was a setting rather than a one-shot
+    // request on the queue.
+    #[serde(alias = "stopAfterCurrent")]
+    Stop,
+    RepeatAll,
+    /// Append tracks similar to the last one and keep playing, if the provider supports it.
+    PlaySimilar,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverArtSize {
+    Thumb,
+    #[default]
+    Medium,
+    Large,
 }
 
 impl AppConfig {
@@ -26,6 +98,29 @@ impl AppConfig {
         }
     }
 
+    /// Ids of all configured sources, `source_order` first.
+    pub fn ordered_source_ids(&self) -> Vec<String> {
+        let configured: Vec<&str> = self
+            .sources
+            .iter()
+            .map(|s| match s {
+                SourceConfig::Local { id, .. } | SourceConfig::Subsonic { id, .. } => id.as_str(),
+            })
+            .collect();
+        let mut ids: Vec<String> = Vec::new();
+        for id in self
+            .source_order
+            .iter()
+            .map(String::as_str)
+            .chain(configured.clone())
+        {
+            if configured.contains(&id) && !ids.iter().any(|i| i == id) {
+                ids.push(id.to_string());
+            }
+        }
+        ids
+    }
+
     pub fn save(&self, app: &AppHandle) -> Result<(), String> {
         let store = app.store("config.json").map_err(|e| e.to_string())?;
         let val = serde_json::to_value(self).map_err(|e| e.to_string())?;
@@ -43,6 +138,21 @@ impl Default for AppConfig {
             audio_engine: AudioBackend::default(),
             lastfm_session: None,
             discord_rpc: Some(DiscordRpcConfig::default()),
+            default_volume: default_volume(),
+            hide_explicit: false,
+            cover_art_size: CoverArtSize::default(),
+            end_of_queue: EndOfQueueBehavior::default(),
+            unknown_artist_label: default_unknown_artist_label(),
+            unknown_album_label: default_unknown_album_label(),
+            prefer_local_art: true,
+            overwrite_artist_bio: false,
+            default_provider_id: None,
+            auto_skip_threshold: None,
+            max_queue_size: None,
+            use_sort_names: true,
+            sort_articles: default_sort_articles(),
+            source_order: Vec::new(),
+            lyrics_sources: default_lyrics_sources(),
         }
     }
 }
@@ -87,6 +197,39 @@ fn default_true() -> bool {
     true
 }
 
+fn default_max_concurrent_requests() -> u32 {
+    6
+}
+
+fn default_request_timeout_secs() -> u64 {
+    30
+}
+
+fn default_sort_articles() -> Vec<String> {
+    vec!["The".to_string(), "A".to_string(), "An".to_string()]
+}
+
+fn default_lyrics_sources() -> Vec<LyricsSource> {
+    vec![
+        LyricsSource::Embedded,
+        LyricsSource::Sidecar,
+        LyricsSource::Provider,
+        LyricsSource::Lrclib,
+    ]
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+fn default_unknown_artist_label() -> String {
+    "Unknown Artist".to_string()
+}
+
+fn default_unknown_album_label() -> String {
+    "Unknown Album".to_string()
+}
+
 fn default_details_format() -> String {
     "{track}".to_string()
 }
@@ -112,6 +255,22 @@ pub enum SourceConfig {
         name: String,
         path: String,
         enabled: bool,
+        #[serde(default)]
+        follow_symlinks: bool,
+        #[serde(default)]
+        filename_pattern: Option<String>,
+        /// Guess the real charset of tags that were stored as Latin-1 but hold
+        /// another legacy codepage, and transcode them during scan.
+        #[serde(default)]
+        detect_tag_encoding: bool,
+        /// Forces a legacy charset (e.g. `windows-1251`) for such tags instead of guessing.
+        #[serde(default)]
+        tag_encoding: Option<String>,
+        /// Words that mark a disc suffix on album titles, as in "Album (Disc 2)"
+        /// or "Album CD2". Defaults to disc/disk/cd; an empty list turns off
+        /// folding such albums together.
+        #[serde(default)]
+        disc_suffixes: Option<Vec<String>>,
     },
     Subsonic {
         id: String,
@@ -121,6 +280,10 @@ pub enum SourceConfig {
         token: String,
         salt: String,
         enabled: bool,
+        #[serde(default = "default_max_concurrent_requests")]
+        max_concurrent_requests: u32,
+        #[serde(default = "default_request_timeout_secs")]
+        request_timeout_secs: u64,
     },
 }
 
@@ -137,9 +300,102 @@ impl Default for AudioBackend {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct MpvConfig {
     pub cache_mb: Option<u32>,
     pub hardware_decoding: bool,
     pub audio_device: Option<String>,
+    /// Volume ramp when pausing and resuming, in ms. 0 switches instantly.
+    #[serde(default)]
+    pub fade_on_resume_ms: u32,
+    /// Play/pause requests arriving within this many ms of the last one are
+    /// held back and only the latest is applied, so rapid toggles (e.g. from
+    /// media keys) don't stutter. 0 applies every request immediately.
+    #[serde(default)]
+    pub toggle_debounce_ms: u32,
+    #[serde(default)]
+    pub night_mode: bool,
+    /// Applies the files' ReplayGain tags.
+    #[serde(default)]
+    pub replaygain: ReplayGainMode,
+    /// Ends the filter chain with a limiter so gain stages can't push the output
+    /// past full scale, and keeps ReplayGain and per-track gain below each
+    /// track's peak.
+    #[serde(default)]
+    pub prevent_clipping: bool,
+    #[serde(default)]
+    pub log_level: MpvLogLevel,
+    /// Reopens http(s) streams after a dropped connection instead of ending the
+    /// track. Local files are unaffected.
+    #[serde(default = "default_true")]
+    pub stream_reconnect: bool,
+    /// Longest wait between reconnect attempts, in seconds.
+    #[serde(default = "default_reconnect_delay_max")]
+    pub reconnect_delay_max: u32,
+}
+
+fn default_reconnect_delay_max() -> u32 {
+    5
+}
+
+impl Default for MpvConfig {
+    fn default() -> Self {
+        Self {
+            cache_mb: None,
+            hardware_decoding: false,
+            audio_device: None,
+            fade_on_resume_ms: 0,
+            toggle_debounce_ms: 0,
+            night_mode: false,
+            replaygain: ReplayGainMode::default(),
+            prevent_clipping: false,
+            log_level: MpvLogLevel::default(),
+            stream_reconnect: true,
+            reconnect_delay_max: default_reconnect_delay_max(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+}
+
+impl ReplayGainMode {
+    pub fn as_mpv_str(&self) -> &'static str {
+        match self {
+            ReplayGainMode::Off => "no",
+            ReplayGainMode::Track => "track",
+            ReplayGainMode::Album => "album",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MpvLogLevel {
+    None,
+    Fatal,
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+}
+
+impl MpvLogLevel {
+    pub fn as_mpv_str(&self) -> &'static str {
+        match self {
+            MpvLogLevel::None => "no",
+            MpvLogLevel::Fatal => "fatal",
+            MpvLogLevel::Error => "error",
+            MpvLogLevel::Warn => "warn",
+            MpvLogLevel::Info => "info",
+            MpvLogLevel::Debug => "debug",
+        }
+    }
 }
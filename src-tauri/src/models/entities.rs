@@ -25,8 +25,28 @@ pub struct Track {
 
     pub genre: Option<String>,
     pub bitrate: Option<u32>,
+    /// Audio codec in upper case (e.g. `FLAC`, `ALAC`, `MP3`), when known.
+    #[sqlx(default)]
+    pub codec: Option<String>,
+    /// ReplayGain track peak (1.0 = full scale), when tagged.
+    #[sqlx(default)]
+    pub peak: Option<f32>,
     pub play_count: u32,
     pub liked: bool,
+    #[sqlx(default)]
+    pub explicit: bool,
+    #[sqlx(default)]
+    pub rating: Option<u8>,
+    /// Times playback was moved on from this track before it finished.
+    #[sqlx(default)]
+    pub skip_count: u32,
+    /// Index within the playlist it was listed from, telling apart repeated
+    /// entries of the same track. `None` outside playlists.
+    #[sqlx(default)]
+    pub playlist_position: Option<u32>,
+    /// Gain set by the user for this track, in dB, applied on top of ReplayGain.
+    #[sqlx(default)]
+    pub gain_db: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type, FromRow)]
@@ -61,9 +81,140 @@ pub struct Playlist {
     #[sqlx(default)]
     pub track_count: u32,
     pub cover_art: Option<String>,
+    #[sqlx(default)]
+    pub description: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistFeature {
+    pub album: Album,
+    pub tracks: Vec<Track>,
+}
+
+/// Places lyrics are looked up, tried in the order configured in `lyrics_sources`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum LyricsSource {
+    /// Lyrics tag inside the audio file.
+    Embedded,
+    /// `.lrc` file next to the audio file.
+    Sidecar,
+    /// The library provider's own API, e.g. Subsonic's lyrics endpoints.
+    Provider,
+    Lrclib,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Lyrics {
+    pub source: LyricsSource,
+    /// LRC text, one `[mm:ss.xx]`-stamped line per lyric line.
+    pub synced: Option<String>,
+    pub plain: Option<String>,
+}
+
+impl Lyrics {
+    /// Wraps lyrics text of unknown format, treating it as synced when it has
+    /// LRC timestamps.
+    pub fn from_text(source: LyricsSource, text: String) -> Option<Self> {
+        if text.trim().is_empty() {
+            return None;
+        }
+        let synced = text.lines().any(|line| {
+            let line = line.trim_start();
+            line.starts_with('[') && line[1..].starts_with(|c: char| c.is_ascii_digit())
+        });
+        Some(if synced {
+            Self {
+                source,
+                synced: Some(text),
+                plain: None,
+            }
+        } else {
+            Self {
+                source,
+                synced: None,
+                plain: Some(text),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ArtworkKind {
+    Front,
+    Back,
+    Booklet,
+    Media,
+    Artist,
+    Other,
+}
+
+impl ArtworkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtworkKind::Front => "front",
+            ArtworkKind::Back => "back",
+            ArtworkKind::Booklet => "booklet",
+            ArtworkKind::Media => "media",
+            ArtworkKind::Artist => "artist",
+            ArtworkKind::Other => "other",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "front" => ArtworkKind::Front,
+            "back" => ArtworkKind::Back,
+            "booklet" => ArtworkKind::Booklet,
+            "media" => ArtworkKind::Media,
+            "artist" => ArtworkKind::Artist,
+            _ => ArtworkKind::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumArtwork {
+    pub kind: ArtworkKind,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PodcastChannel {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub cover_art: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NowPlayingEntry {
+    pub username: String,
+    pub player_name: Option<String>,
+    pub minutes_ago: u32,
+    pub track: Track,
+}
+
+/// Row counts touched by merging one artist into another.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtistMergeResult {
+    pub tracks_updated: u32,
+    pub albums_updated: u32,
+    /// Albums that already existed under the kept artist and were folded into it.
+    pub albums_merged: u32,
+    pub track_artists_updated: u32,
+    pub album_artists_updated: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct Genre {
@@ -71,13 +222,33 @@ pub struct Genre {
     pub track_count: u32,
 }
 
+/// What a provider supports beyond browsing and playback, so the UI can hide
+/// actions that would only fail.
+#[derive(Debug, Clone, Copy, Serialize, Type, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCapabilities {
+    pub can_create_playlist: bool,
+    pub can_rename_playlist: bool,
+    pub can_add_to_playlist: bool,
+    pub can_remove_from_playlist: bool,
+    pub writable_favorites: bool,
+    pub can_rate: bool,
+    /// Has its own similar-tracks source for instant mixes and autoplay.
+    pub has_radio: bool,
+    pub can_scan: bool,
+    pub can_cancel_scan: bool,
+    /// Merging and splitting artists and albums, editing track flags.
+    pub can_edit_library: bool,
+    pub has_podcasts: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct LibraryStats {
     pub album_count: u32,
     pub track_count: u32,
     pub artist_count: u32,
-    pub total_duration: u32,
+    pub total_duration: u64,
     pub average_bitrate: u32,
 }
 
@@ -88,6 +259,237 @@ pub struct UnifiedSearchResult {
     pub albums: Vec<Album>,
     pub artists: Vec<Artist>,
 }
+
+/// One source's share of a combined search, labelled so views can show a
+/// section per source.
+#[derive(Debug, Clone, serde::Serialize, specta::Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceSearchResult {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub result: UnifiedSearchResult,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceTracks {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum TrackSort {
+    #[default]
+    Album,
+    Title,
+    MostPlayed,
+    Newest,
+}
+
+impl TrackSort {
+    pub fn sort(&self, tracks: &mut [Track]) {
+        match self {
+            TrackSort::Album => tracks.sort_by(|a, b| {
+                a.album_title
+                    .cmp(&b.album_title)
+                    .then(a.disc_number.cmp(&b.disc_number))
+                    .then(a.track_number.cmp(&b.track_number))
+            }),
+            TrackSort::Title => tracks.sort_by_key(|t| t.title.to_lowercase()),
+            TrackSort::MostPlayed => tracks.sort_by(|a, b| b.play_count.cmp(&a.play_count)),
+            TrackSort::Newest => tracks.sort_by(|a, b| b.year.cmp(&a.year)),
+        }
+    }
+}
+
+/// Puts an album's tracks in playing order: by disc (none counts as disc 1),
+/// then track number with unnumbered tracks last, then title.
+pub fn sort_album_tracks(tracks: &mut [Track]) {
+    tracks.sort_by_cached_key(|t| {
+        (
+            t.disc_number.unwrap_or(1),
+            t.track_number.is_none(),
+            t.track_number,
+            t.title.to_lowercase(),
+        )
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchType {
+    Tracks,
+    Albums,
+    Artists,
+}
+
+impl SearchType {
+    pub const ALL: [SearchType; 3] = [SearchType::Tracks, SearchType::Albums, SearchType::Artists];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTestResult {
+    pub ok: bool,
+    pub server_version: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackFailure {
+    pub track_id: String,
+    pub track_title: String,
+    pub provider_id: Option<String>,
+    pub error: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type, tauri_specta::Event)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanRootFailed {
+    pub provider_id: String,
+    pub root: String,
+    pub error: String,
+}
+
+/// Emitted when a local library database failed its integrity check and was moved
+/// to `backup_path`, leaving an empty library in its place.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, tauri_specta::Event)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryDatabaseReset {
+    pub provider_id: String,
+    pub backup_path: String,
+    pub errors: String,
+}
+
+/// Emitted by the queue whenever it loads a track into the engine.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, tauri_specta::Event)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackChanged {
+    pub track: Track,
+}
+
+/// Emitted when a scan stops early because `cancel_scan` was called.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, tauri_specta::Event)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanCancelled {
+    pub provider_id: String,
+}
+
+/// One disc of a multi-disc album, as returned by `get_album_discs`.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumDisc {
+    pub disc_number: u32,
+    pub tracks: Vec<Track>,
+}
+
+/// The albums either side of one album in its artist's discography.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AdjacentAlbums {
+    pub prev: Option<Album>,
+    pub next: Option<Album>,
+}
+
+/// One row of a top-N list in a listening report.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportEntry {
+    /// Track or album id; artists are grouped by name and have none.
+    pub id: Option<String>,
+    pub provider_id: Option<String>,
+    pub name: String,
+    pub artist_name: Option<String>,
+    pub plays: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyPlays {
+    /// Local calendar date, `YYYY-MM-DD`.
+    pub date: String,
+    pub plays: u32,
+}
+
+/// Aggregated listening history over a time range.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListeningReport {
+    pub total_plays: u32,
+    /// Sum of the durations of played tracks, so skips count as full listens.
+    pub total_listening_secs: u64,
+    pub top_artists: Vec<ReportEntry>,
+    pub top_albums: Vec<ReportEntry>,
+    pub top_tracks: Vec<ReportEntry>,
+    pub daily: Vec<DailyPlays>,
+}
+
+/// What a cover maintenance pass changed.
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverCleanupResult {
+    pub files_removed: u32,
+    pub bytes_reclaimed: u64,
+    /// Albums whose missing cover file was re-extracted from one of their tracks.
+    pub albums_relinked: u32,
+    /// Albums whose cover was missing and couldn't be recovered, now left empty.
+    pub albums_cleared: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceKind {
+    Local,
+    Subsonic,
+}
+
+/// A configured source and whether it's currently usable.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceStatus {
+    pub id: String,
+    pub name: String,
+    pub kind: SourceKind,
+    pub enabled: bool,
+    /// Loaded and passing its health check. Always false for disabled sources.
+    pub online: bool,
+}
+
+/// Outcome of scanning a single provider as part of a bulk scan.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderScanResult {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum LibraryChangeKind {
+    /// A scan finished; tracks may have been added, updated or removed.
+    TracksAdded,
+    /// Tracks were removed without a scan, e.g. those under a deleted folder.
+    TracksRemoved,
+    TrackEdited,
+    PlaylistChanged,
+    FavoritesChanged,
+    AlbumsChanged,
+    ArtistsChanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type, tauri_specta::Event)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryChanged {
+    pub provider_id: String,
+    pub kind: LibraryChangeKind,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type, tauri_specta::Event)]
 #[serde(tag = "type", content = "data")]
 pub enum PlayerEvent {
@@ -95,6 +497,81 @@ pub enum PlayerEvent {
     DurationChange(f64),
     Paused,
     Playing,
+    /// Playback was stopped by the user. Unlike `Paused`, nothing is loaded in the
+    /// engine anymore; the queue and its current index are kept.
+    Stopped,
     Ended,
+    /// The stream stalled waiting for network data (`true`) or resumed (`false`).
+    /// Distinct from `Paused`, which is always a user action.
+    Buffering(bool),
+    /// Cache fill percentage (0-100) while buffering.
+    BufferProgress(f64),
     Error(String),
+    EngineRestarted,
+    EngineLog(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(title: &str, disc_number: Option<u32>, track_number: Option<u32>) -> Track {
+        Track {
+            id: title.to_string(),
+            provider_id: None,
+            title: title.to_string(),
+            artist_id: String::new(),
+            artist_name: String::new(),
+            album_id: String::new(),
+            album_title: String::new(),
+            duration_sec: 180,
+            track_number,
+            disc_number,
+            year: None,
+            genre: None,
+            bitrate: None,
+            codec: None,
+            peak: None,
+            play_count: 0,
+            liked: false,
+            explicit: false,
+            rating: None,
+            skip_count: 0,
+            playlist_position: None,
+            gain_db: None,
+        }
+    }
+
+    fn titles(tracks: &[Track]) -> Vec<&str> {
+        tracks.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn sorts_shuffled_tracks_by_disc_then_number() {
+        let mut tracks = vec![
+            track("2-02", Some(2), Some(2)),
+            track("1-03", Some(1), Some(3)),
+            track("2-01", Some(2), Some(1)),
+            track("1-01", None, Some(1)),
+            track("1-02", Some(1), Some(2)),
+        ];
+
+        sort_album_tracks(&mut tracks);
+
+        assert_eq!(titles(&tracks), ["1-01", "1-02", "1-03", "2-01", "2-02"]);
+    }
+
+    #[test]
+    fn puts_unnumbered_tracks_last_in_title_order() {
+        let mut tracks = vec![
+            track("bonus", None, None),
+            track("second", None, Some(2)),
+            track("Applause", None, None),
+            track("first", None, Some(1)),
+        ];
+
+        sort_album_tracks(&mut tracks);
+
+        assert_eq!(titles(&tracks), ["first", "second", "Applause", "bonus"]);
+    }
 }
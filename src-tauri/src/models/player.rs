@@ -8,6 +8,7 @@ pub struct PlayerState {
     pub position: f64,
     pub duration: f64,
     pub volume: f32,
+    pub muted: bool,
     pub exclusive: bool,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
@@ -20,6 +21,24 @@ pub struct Queue {
     pub repeat: RepeatMode,
 }
 
+/// Whether the next/previous controls would do anything, as returned by
+/// `get_navigation_state`.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigationState {
+    pub has_next: bool,
+    pub has_prev: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentContext {
+    pub track: super::entities::Track,
+    pub album: Option<super::entities::Album>,
+    pub artist: Option<super::entities::Artist>,
+    pub provider_id: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
 #[serde(rename_all = "camelCase")]
 pub enum RepeatMode {
@@ -38,10 +57,27 @@ pub struct AudioDevice {
     pub is_current: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineInfo {
+    pub backend: String,
+    pub version: Option<String>,
+    pub audio_codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub sample_format: Option<String>,
+    pub channels: Option<String>,
+    pub hwdec: Option<String>,
+    pub audio_device: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Type, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct PersistedQueue {
     pub tracks: Vec<String>,
+    /// Owning provider of each entry in `tracks`, so ids shared across
+    /// providers restore from the right one.
+    #[serde(default)]
+    pub track_providers: Vec<Option<String>>,
     pub current_index: Option<usize>,
     pub repeat_mode: RepeatMode,
     pub shuffle: bool,
@@ -1 +1,2 @@
 pub mod mpv;
+pub mod switchable;
@@ -1,45 +1,232 @@
 use crate::models::config::MpvConfig;
 use crate::models::entities::PlayerEvent;
-use crate::models::{AudioDevice, PlayerState};
+use crate::models::{AudioDevice, EngineInfo, PlayerState};
 use crate::traits::{AudioEngine, AudioStream};
 use async_trait::async_trait;
 use libmpv2::{
     events::{Event, PropertyData},
     Mpv,
 };
-use std::time::Duration;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc, oneshot};
 
+/// libmpv2 doesn't wrap `mpv_request_log_messages`, so log capture goes through the
+/// raw handle. Messages at `min_level` and above then arrive as `Event::LogMessage`.
+fn request_log_messages(mpv: &Mpv, min_level: &str) {
+    let Ok(level) = std::ffi::CString::new(min_level) else {
+        return;
+    };
+    let res = unsafe { libmpv2_sys::mpv_request_log_messages(mpv.ctx.as_ptr(), level.as_ptr()) };
+    if res < 0 {
+        log::warn!("MPV: Failed to request log messages: error {}", res);
+    }
+}
+
+fn is_network_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
 #[derive(serde::Deserialize)]
 struct MpvDeviceEntry {
     name: String,
     description: String,
 }
 
+/// Portion of the file to play, for tracks cut from a larger file.
+#[derive(Clone, Copy)]
+struct Segment {
+    start: f64,
+    end: Option<f64>,
+}
+
 enum EngineCommand {
-    Load { url: String, auto_play: bool },
+    Load {
+        url: String,
+        auto_play: bool,
+        segment: Option<Segment>,
+    },
     Play,
     Pause,
     Stop,
     Seek(f64),
     SetVolume(f32),
+    SetMute(Option<bool>),
     GetState(oneshot::Sender<PlayerState>),
     GetAudioDevices(oneshot::Sender<Result<Vec<AudioDevice>, String>>),
     SetAudioDevice(Option<String>),
     ToggleExclusiveMode(Option<bool>),
+    SetNightMode(bool),
+    SetPreventClipping(bool),
+    SetTrackGain {
+        gain_db: Option<f32>,
+        peak: Option<f32>,
+    },
+    GetEngineInfo(oneshot::Sender<EngineInfo>),
+}
+
+const NIGHT_MODE_FILTER: &str = "lavfi=[dynaudnorm=f=250:g=31:p=0.9]";
+const LIMITER_FILTER: &str = "lavfi=[alimiter=limit=0.95:level=disabled]";
+
+#[derive(Default)]
+struct AudioFilters {
+    night_mode: bool,
+    prevent_clipping: bool,
+    track_gain: Option<f32>,
+    track_peak: Option<f32>,
+}
+
+impl AudioFilters {
+    /// The per-track gain, lowered when clipping prevention is on so the track's
+    /// peak stays at full scale or below. Tracks without a ReplayGain peak tag
+    /// get the gain as is and rely on the limiter instead.
+    fn effective_track_gain(&self) -> f32 {
+        let gain = self.track_gain.unwrap_or(0.0);
+        match self.track_peak.filter(|p| *p > 0.0) {
+            Some(peak) if self.prevent_clipping => gain.min(-20.0 * peak.log10()),
+            _ => gain,
+        }
+    }
+
+    fn to_af_string(&self) -> String {
+        let mut chain = Vec::new();
+        let gain = self.effective_track_gain();
+        if gain != 0.0 {
+            chain.push(format!("lavfi=[volume={}dB]", gain));
+        }
+        if self.night_mode {
+            chain.push(NIGHT_MODE_FILTER.to_string());
+        }
+        // Must stay last so it catches gain added by every filter before it.
+        if self.prevent_clipping {
+            chain.push(LIMITER_FILTER.to_string());
+        }
+        chain.join(",")
+    }
+}
+
+struct VolumeFade {
+    from: f64,
+    to: f64,
+    started: Instant,
+    duration: Duration,
+    pause_on_finish: bool,
+}
+
+impl VolumeFade {
+    fn progress(&self) -> f64 {
+        (self.started.elapsed().as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+    }
+
+    fn level(&self) -> f64 {
+        self.from + (self.to - self.from) * self.progress()
+    }
+}
+
+/// Unpauses, fading in from silence (or from wherever a pause fade got to).
+fn resume(mpv: &Mpv, fade: &mut Option<VolumeFade>, volume: f32, paused: bool, duration: Duration) {
+    let pausing = fade.as_ref().is_some_and(|f| f.pause_on_finish);
+    if !duration.is_zero() && (paused || pausing) {
+        let from = match fade {
+            Some(f) => f.level(),
+            None => 0.0,
+        };
+        let _ = mpv.set_property("volume", from);
+        *fade = Some(VolumeFade {
+            from,
+            to: volume as f64 * 100.0,
+            started: Instant::now(),
+            duration,
+            pause_on_finish: false,
+        });
+    }
+    let _ = mpv.set_property("pause", false);
+}
+
+/// Pauses, after fading out when a fade duration is set.
+fn pause(mpv: &Mpv, fade: &mut Option<VolumeFade>, volume: f32, paused: bool, duration: Duration) {
+    if !duration.is_zero() && !paused {
+        let from = match fade {
+            Some(f) => f.level(),
+            None => volume as f64 * 100.0,
+        };
+        *fade = Some(VolumeFade {
+            from,
+            to: 0.0,
+            started: Instant::now(),
+            duration,
+            pause_on_finish: true,
+        });
+    } else {
+        let _ = mpv.set_property("pause", true);
+    }
+}
+
+/// Restarts allowed in a crash loop, i.e. with each crash following the previous
+/// restart within `RESTART_RESET_AFTER`.
+const MAX_ENGINE_RESTARTS: u32 = 3;
+/// An actor that ran this long before dying starts the restart count over.
+const RESTART_RESET_AFTER: Duration = Duration::from_secs(60);
+/// Seeks stop this far short of the end, since seeking onto the end makes mpv
+/// finish the file and the queue advance, even while paused.
+const SEEK_END_MARGIN: f64 = 0.5;
+
+/// Keeps a seek target inside the track. The end is only enforced once the
+/// duration is known.
+fn clamp_seek(target: f64, duration: f64) -> f64 {
+    let target = target.max(0.0);
+    if duration > SEEK_END_MARGIN {
+        target.min(duration - SEEK_END_MARGIN)
+    } else {
+        target
+    }
+}
+
+#[derive(Default)]
+struct EngineSnapshot {
+    url: Option<String>,
+    segment: Option<Segment>,
+    position: f64,
+    volume: f32,
+    paused: bool,
+    track_gain: Option<f32>,
+    track_peak: Option<f32>,
 }
 
 #[derive(Clone)]
 pub struct MpvPlayer {
-    cmd_tx: mpsc::Sender<EngineCommand>,
+    cmd_tx: Arc<Mutex<mpsc::Sender<EngineCommand>>>,
     event_tx: broadcast::Sender<PlayerEvent>,
+    config: Arc<Mutex<MpvConfig>>,
+    snapshot: Arc<Mutex<EngineSnapshot>>,
+    restarts: Arc<AtomicU32>,
+    last_restart: Arc<Mutex<Option<Instant>>>,
 }
 
 impl MpvPlayer {
     pub fn new(config: MpvConfig) -> anyhow::Result<Self> {
-        let (cmd_tx, mut cmd_rx) = mpsc::channel(32);
         let (event_tx, _) = broadcast::channel(128);
-        let event_tx_actor = event_tx.clone();
+        let snapshot = Arc::new(Mutex::new(EngineSnapshot::default()));
+        let cmd_tx = Self::spawn_actor(config.clone(), event_tx.clone(), snapshot.clone())?;
+
+        Ok(Self {
+            cmd_tx: Arc::new(Mutex::new(cmd_tx)),
+            event_tx,
+            config: Arc::new(Mutex::new(config)),
+            snapshot,
+            restarts: Arc::new(AtomicU32::new(0)),
+            last_restart: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    fn spawn_actor(
+        config: MpvConfig,
+        event_tx_actor: broadcast::Sender<PlayerEvent>,
+        snapshot: Arc<Mutex<EngineSnapshot>>,
+    ) -> anyhow::Result<mpsc::Sender<EngineCommand>> {
+        let (cmd_tx, mut cmd_rx) = mpsc::channel(32);
 
         std::thread::Builder::new()
             .name("mpv-actor".to_string())
@@ -59,12 +246,43 @@ impl MpvPlayer {
                     }
                 }
 
+                let mut filters = AudioFilters {
+                    night_mode: config.night_mode,
+                    prevent_clipping: config.prevent_clipping,
+                    track_gain: None,
+                    track_peak: None,
+                };
+                if let Err(e) = mpv.set_property("af", filters.to_af_string()) {
+                    log::warn!("MPV: Failed to set audio filters: {}", e);
+                }
+
+                if let Err(e) = mpv.set_property("replaygain", config.replaygain.as_mpv_str()) {
+                    log::warn!("MPV: Failed to set replaygain: {}", e);
+                }
+                // With `replaygain-clip` on, ReplayGain uses the files' peak tags to
+                // keep its gain below clipping.
+                if let Err(e) = mpv.set_property("replaygain-clip", config.prevent_clipping) {
+                    log::warn!("MPV: Failed to set replaygain-clip: {}", e);
+                }
+
+                // Only set while a network stream is loading; see `Load`.
+                let reconnect_options = if config.stream_reconnect {
+                    format!(
+                        "reconnect=1,reconnect_streamed=1,reconnect_delay_max={}",
+                        config.reconnect_delay_max
+                    )
+                } else {
+                    String::new()
+                };
+
                 if let Some(device) = &config.audio_device {
                     if let Err(e) = mpv.set_property("audio-device", device.clone()) {
                         log::error!("MPV: Failed to set audio device '{}': {}", device, e);
                     }
                 }
 
+                request_log_messages(&mpv, config.log_level.as_mpv_str());
+
                 if let Err(e) = mpv.observe_property("time-pos", libmpv2::Format::Double, 0) {
                     log::warn!("MPV: Failed to observe time-pos: {}", e);
                 }
@@ -77,18 +295,51 @@ impl MpvPlayer {
                 if let Err(e) = mpv.observe_property("volume", libmpv2::Format::Double, 0) {
                     log::warn!("MPV: Failed to observe volume: {}", e);
                 }
+                if let Err(e) = mpv.observe_property("mute", libmpv2::Format::Flag, 0) {
+                    log::warn!("MPV: Failed to observe mute: {}", e);
+                }
                 if let Err(e) = mpv.observe_property("audio-exclusive", libmpv2::Format::Flag, 0) {
                     log::warn!("MPV: Failed to observe audio-exclusive: {}", e);
                 }
+                if let Err(e) = mpv.observe_property("paused-for-cache", libmpv2::Format::Flag, 0) {
+                    log::warn!("MPV: Failed to observe paused-for-cache: {}", e);
+                }
+                if let Err(e) =
+                    mpv.observe_property("cache-buffering-state", libmpv2::Format::Int64, 0)
+                {
+                    log::warn!("MPV: Failed to observe cache-buffering-state: {}", e);
+                }
 
                 let mut cached_state = PlayerState::default();
+                let fade_duration = Duration::from_millis(config.fade_on_resume_ms as u64);
+                let mut fade: Option<VolumeFade> = None;
+                let toggle_debounce = Duration::from_millis(config.toggle_debounce_ms as u64);
+                let mut last_toggle: Option<Instant> = None;
+                // Play (false) or pause (true) held back by the debounce.
+                let mut pending_pause: Option<bool> = None;
+                let mut buffering = false;
+                // mpv reports positions within the whole file; events and seeks use
+                // positions within the segment.
+                let mut segment: Option<Segment> = None;
+                let offset = |segment: &Option<Segment>| segment.map_or(0.0, |s| s.start);
 
                 'actor: loop {
-                    while let Some(Ok(ev)) = mpv.wait_event(0.01) {
+                    while let Some(res) = mpv.wait_event(0.01) {
+                        // Failed loads (e.g. an expired stream URL) surface as errors
+                        // here rather than as an end-file event.
+                        let ev = match res {
+                            Ok(ev) => ev,
+                            Err(e) => {
+                                let _ = event_tx_actor
+                                    .send(PlayerEvent::Error(format!("Playback failed: {}", e)));
+                                continue;
+                            }
+                        };
                         match ev {
                             Event::PropertyChange { name, change, .. } => match name {
                                 "time-pos" => {
                                     if let PropertyData::Double(v) = change {
+                                        let v = (v - offset(&segment)).max(0.0);
                                         cached_state.position = v;
                                         let _ = event_tx_actor.send(PlayerEvent::TimeUpdate(v));
                                     }
@@ -105,13 +356,24 @@ impl MpvPlayer {
                                 }
                                 "duration" => {
                                     if let PropertyData::Double(v) = change {
+                                        let v = match segment {
+                                            Some(s) => s.end.unwrap_or(v) - s.start,
+                                            None => v,
+                                        };
                                         cached_state.duration = v;
                                         let _ = event_tx_actor.send(PlayerEvent::DurationChange(v));
                                     }
                                 }
                                 "volume" => {
                                     if let PropertyData::Double(v) = change {
-                                        cached_state.volume = (v / 100.0) as f32;
+                                        if fade.is_none() {
+                                            cached_state.volume = (v / 100.0) as f32;
+                                        }
+                                    }
+                                }
+                                "mute" => {
+                                    if let PropertyData::Flag(v) = change {
+                                        cached_state.muted = v;
                                     }
                                 }
                                 "audio-exclusive" => {
@@ -119,6 +381,23 @@ impl MpvPlayer {
                                         cached_state.exclusive = v;
                                     }
                                 }
+                                "paused-for-cache" => {
+                                    if let PropertyData::Flag(v) = change {
+                                        if v != buffering {
+                                            buffering = v;
+                                            let _ = event_tx_actor.send(PlayerEvent::Buffering(v));
+                                        }
+                                    }
+                                }
+                                "cache-buffering-state" => {
+                                    // Only meaningful while stalled; mpv keeps updating it otherwise.
+                                    if let PropertyData::Int64(v) = change {
+                                        if buffering {
+                                            let _ = event_tx_actor
+                                                .send(PlayerEvent::BufferProgress(v as f64));
+                                        }
+                                    }
+                                }
                                 _ => {
                                     log::warn!("MPV: Unhandled property change: {}", name);
                                 }
@@ -126,14 +405,107 @@ impl MpvPlayer {
                             Event::EndFile(0) => {
                                 let _ = event_tx_actor.send(PlayerEvent::Ended);
                             }
+                            Event::LogMessage {
+                                prefix,
+                                level,
+                                text,
+                                ..
+                            } => {
+                                let text = text.trim_end();
+                                match level {
+                                    "fatal" | "error" => log::error!("mpv[{}]: {}", prefix, text),
+                                    "warn" => log::warn!("mpv[{}]: {}", prefix, text),
+                                    "info" => log::info!("mpv[{}]: {}", prefix, text),
+                                    _ => log::debug!("mpv[{}]: {}", prefix, text),
+                                }
+                                if matches!(level, "fatal" | "error" | "warn") {
+                                    let _ = event_tx_actor.send(PlayerEvent::EngineLog(format!(
+                                        "[{}] {}: {}",
+                                        prefix, level, text
+                                    )));
+                                }
+                            }
                             Event::Shutdown => break 'actor,
                             _ => {}
                         }
                     }
 
+                    if let Some(f) = &fade {
+                        let _ = mpv.set_property("volume", f.level());
+                        if f.progress() >= 1.0 {
+                            if f.pause_on_finish {
+                                let _ = mpv.set_property("pause", true);
+                                let _ = mpv
+                                    .set_property("volume", cached_state.volume as f64 * 100.0);
+                            }
+                            fade = None;
+                        }
+                    }
+
+                    if let Some(should_pause) = pending_pause {
+                        if !matches!(last_toggle, Some(t) if t.elapsed() < toggle_debounce) {
+                            let toggle = if should_pause { pause } else { resume };
+                            toggle(
+                                &mpv,
+                                &mut fade,
+                                cached_state.volume,
+                                cached_state.paused,
+                                fade_duration,
+                            );
+                            last_toggle = Some(Instant::now());
+                            pending_pause = None;
+                        }
+                    }
+
+                    if let Ok(mut snap) = snapshot.lock() {
+                        snap.position = cached_state.position;
+                        snap.volume = cached_state.volume;
+                        snap.paused = cached_state.paused;
+                    }
+
                     match cmd_rx.try_recv() {
                         Ok(cmd) => match cmd {
-                            EngineCommand::Load { url, auto_play } => {
+                            EngineCommand::Load {
+                                url,
+                                auto_play,
+                                segment: new_segment,
+                            } => {
+                                if let Ok(mut snap) = snapshot.lock() {
+                                    snap.url = Some(url.clone());
+                                    snap.segment = new_segment;
+                                }
+                                // `start`/`end` apply to the next file loaded, so they're
+                                // reset for whole files too.
+                                let (start, end) = match new_segment {
+                                    Some(s) => (
+                                        s.start.to_string(),
+                                        s.end.map_or("none".to_string(), |e| e.to_string()),
+                                    ),
+                                    None => ("none".to_string(), "none".to_string()),
+                                };
+                                if let Err(e) = mpv.set_property("start", start) {
+                                    log::warn!("MPV: Failed to set start: {}", e);
+                                }
+                                if let Err(e) = mpv.set_property("end", end) {
+                                    log::warn!("MPV: Failed to set end: {}", e);
+                                }
+                                let lavf_options = if is_network_url(&url) {
+                                    reconnect_options.clone()
+                                } else {
+                                    String::new()
+                                };
+                                if let Err(e) = mpv.set_property("stream-lavf-o", lavf_options) {
+                                    log::warn!("MPV: Failed to set stream-lavf-o: {}", e);
+                                }
+                                segment = new_segment;
+                                pending_pause = None;
+                                // Stale until mpv reports the new file's duration, and
+                                // `Seek` must not clamp to the previous track's length.
+                                cached_state.duration = 0.0;
+                                if fade.take().is_some() {
+                                    let _ = mpv
+                                        .set_property("volume", cached_state.volume as f64 * 100.0);
+                                }
                                 if let Err(e) = mpv.command("loadfile", &[&url, "replace"]) {
                                     log::error!("MPV Load Error: {}", e);
                                 } else {
@@ -149,20 +521,64 @@ impl MpvPlayer {
                                     let _ = event_tx_actor.send(event);
                                 }
                             }
-                            EngineCommand::Play => {
-                                let _ = mpv.set_property("pause", false);
-                            }
-                            EngineCommand::Pause => {
-                                let _ = mpv.set_property("pause", true);
+                            EngineCommand::Play | EngineCommand::Pause => {
+                                let should_pause = matches!(cmd, EngineCommand::Pause);
+                                if matches!(last_toggle, Some(t) if t.elapsed() < toggle_debounce) {
+                                    pending_pause = Some(should_pause);
+                                } else {
+                                    let toggle = if should_pause { pause } else { resume };
+                                    toggle(
+                                        &mpv,
+                                        &mut fade,
+                                        cached_state.volume,
+                                        cached_state.paused,
+                                        fade_duration,
+                                    );
+                                    last_toggle = Some(Instant::now());
+                                    pending_pause = None;
+                                }
                             }
                             EngineCommand::Stop => {
+                                pending_pause = None;
+                                if fade.take().is_some() {
+                                    let _ = mpv
+                                        .set_property("volume", cached_state.volume as f64 * 100.0);
+                                }
                                 let _ = mpv.command("stop", &[]);
+                                cached_state.position = 0.0;
+                                if let Ok(mut snap) = snapshot.lock() {
+                                    snap.url = None;
+                                    snap.segment = None;
+                                }
+                                let _ = event_tx_actor.send(PlayerEvent::Stopped);
                             }
                             EngineCommand::Seek(t) => {
-                                let _ = mpv.command("seek", &[&t.to_string(), "absolute"]);
+                                let t = clamp_seek(t, cached_state.duration);
+                                let target = t + offset(&segment);
+                                if mpv
+                                    .command("seek", &[&target.to_string(), "absolute"])
+                                    .is_ok()
+                                {
+                                    cached_state.position = t;
+                                    let _ = event_tx_actor.send(PlayerEvent::TimeUpdate(t));
+                                }
                             }
                             EngineCommand::SetVolume(v) => {
-                                let _ = mpv.set_property("volume", (v * 100.0) as i64);
+                                cached_state.volume = v;
+                                match &mut fade {
+                                    Some(f) if !f.pause_on_finish => f.to = v as f64 * 100.0,
+                                    Some(_) => {}
+                                    None => {
+                                        let _ = mpv.set_property("volume", (v * 100.0) as i64);
+                                    }
+                                }
+                            }
+                            EngineCommand::SetMute(muted) => {
+                                let val = muted.unwrap_or(!cached_state.muted);
+                                if let Err(e) = mpv.set_property("mute", val) {
+                                    log::error!("MPV: Failed to set mute '{}': {}", val, e);
+                                }
+                                cached_state.muted = val;
                             }
                             EngineCommand::GetState(tx) => {
                                 let _ = tx.send(cached_state.clone());
@@ -211,6 +627,57 @@ impl MpvPlayer {
                                 }
                                 cached_state.exclusive = val;
                             }
+                            EngineCommand::SetNightMode(enabled) => {
+                                filters.night_mode = enabled;
+                                let af = filters.to_af_string();
+                                if let Err(e) = mpv.set_property("af", af.clone()) {
+                                    log::error!("MPV: Failed to set audio filters '{}': {}", af, e);
+                                }
+                            }
+                            EngineCommand::SetPreventClipping(enabled) => {
+                                filters.prevent_clipping = enabled;
+                                let af = filters.to_af_string();
+                                if let Err(e) = mpv.set_property("af", af.clone()) {
+                                    log::error!("MPV: Failed to set audio filters '{}': {}", af, e);
+                                }
+                                let _ = mpv.set_property("replaygain-clip", enabled);
+                            }
+                            EngineCommand::SetTrackGain { gain_db, peak } => {
+                                if let Ok(mut snap) = snapshot.lock() {
+                                    snap.track_gain = gain_db;
+                                    snap.track_peak = peak;
+                                }
+                                if filters.track_gain != gain_db || filters.track_peak != peak {
+                                    filters.track_gain = gain_db;
+                                    filters.track_peak = peak;
+                                    let af = filters.to_af_string();
+                                    if let Err(e) = mpv.set_property("af", af.clone()) {
+                                        log::error!(
+                                            "MPV: Failed to set audio filters '{}': {}",
+                                            af,
+                                            e
+                                        );
+                                    }
+                                }
+                            }
+                            EngineCommand::GetEngineInfo(tx) => {
+                                let string_prop =
+                                    |name: &str| mpv.get_property::<String>(name).ok();
+                                let info = EngineInfo {
+                                    backend: "mpv".to_string(),
+                                    version: string_prop("mpv-version"),
+                                    audio_codec: string_prop("audio-codec-name"),
+                                    sample_rate: mpv
+                                        .get_property::<i64>("audio-params/samplerate")
+                                        .ok()
+                                        .map(|v| v as u32),
+                                    sample_format: string_prop("audio-params/format"),
+                                    channels: string_prop("audio-params/channels"),
+                                    hwdec: string_prop("hwdec-current"),
+                                    audio_device: string_prop("audio-device"),
+                                };
+                                let _ = tx.send(info);
+                            }
                         },
                         Err(mpsc::error::TryRecvError::Empty) => {
                             std::thread::sleep(Duration::from_millis(16));
@@ -220,14 +687,79 @@ impl MpvPlayer {
                 }
             })?;
 
-        Ok(Self { cmd_tx, event_tx })
+        Ok(cmd_tx)
+    }
+
+    fn restart(&self) -> Result<(), String> {
+        let mut current_tx = self.cmd_tx.lock().map_err(|e| e.to_string())?;
+        if !current_tx.is_closed() {
+            return Ok(());
+        }
+
+        {
+            let mut last_restart = self.last_restart.lock().map_err(|e| e.to_string())?;
+            if last_restart.is_some_and(|t| t.elapsed() >= RESTART_RESET_AFTER) {
+                self.restarts.store(0, Ordering::SeqCst);
+            }
+            *last_restart = Some(Instant::now());
+        }
+        let attempt = self.restarts.fetch_add(1, Ordering::SeqCst) + 1;
+        if attempt > MAX_ENGINE_RESTARTS {
+            return Err("Audio engine actor died".to_string());
+        }
+        log::warn!(
+            "MPV: Actor died, restarting (attempt {}/{})",
+            attempt,
+            MAX_ENGINE_RESTARTS
+        );
+
+        let config = self.config.lock().map_err(|e| e.to_string())?.clone();
+        let (url, segment, position, volume, paused, track_gain, track_peak) = {
+            let snap = self.snapshot.lock().map_err(|e| e.to_string())?;
+            (
+                snap.url.clone(),
+                snap.segment,
+                snap.position,
+                snap.volume,
+                snap.paused,
+                snap.track_gain,
+                snap.track_peak,
+            )
+        };
+
+        let cmd_tx = Self::spawn_actor(config, self.event_tx.clone(), self.snapshot.clone())
+            .map_err(|e| format!("Failed to restart audio engine: {}", e))?;
+
+        let _ = cmd_tx.try_send(EngineCommand::SetVolume(volume));
+        let _ = cmd_tx.try_send(EngineCommand::SetTrackGain {
+            gain_db: track_gain,
+            peak: track_peak,
+        });
+        if let Some(url) = url {
+            let _ = cmd_tx.try_send(EngineCommand::Load {
+                url,
+                auto_play: !paused,
+                segment,
+            });
+            let _ = cmd_tx.try_send(EngineCommand::Seek(position));
+        }
+
+        *current_tx = cmd_tx;
+        let _ = self.event_tx.send(PlayerEvent::EngineRestarted);
+        Ok(())
     }
 
     async fn send(&self, cmd: EngineCommand) -> Result<(), String> {
-        self.cmd_tx
-            .send(cmd)
-            .await
-            .map_err(|_| "Audio engine actor died".to_string())
+        let cmd_tx = self.cmd_tx.lock().map_err(|e| e.to_string())?.clone();
+        if let Err(mpsc::error::SendError(cmd)) = cmd_tx.send(cmd).await {
+            self.restart()?;
+            let cmd_tx = self.cmd_tx.lock().map_err(|e| e.to_string())?.clone();
+            return cmd_tx
+                .send(cmd)
+                .await
+                .map_err(|_| "Audio engine actor died".to_string());
+        }
+        Ok(())
     }
 }
 
@@ -235,7 +767,22 @@ impl MpvPlayer {
 impl AudioEngine for MpvPlayer {
     async fn load(&self, stream: AudioStream, auto_play: bool) -> Result<(), String> {
         match stream {
-            AudioStream::Url(url) => self.send(EngineCommand::Load { url, auto_play }).await,
+            AudioStream::Url(url) => {
+                self.send(EngineCommand::Load {
+                    url,
+                    auto_play,
+                    segment: None,
+                })
+                .await
+            }
+            AudioStream::Segment { url, start, end } => {
+                self.send(EngineCommand::Load {
+                    url,
+                    auto_play,
+                    segment: Some(Segment { start, end }),
+                })
+                .await
+            }
             AudioStream::Bytes(_) => Err(
                 "MpvPlayer: Raw byte streams are not supported in this configuration.".to_string(),
             ),
@@ -258,9 +805,15 @@ impl AudioEngine for MpvPlayer {
         self.send(EngineCommand::SetVolume(vol)).await
     }
 
+    async fn toggle_mute(&self, muted: Option<bool>) -> Result<bool, String> {
+        self.send(EngineCommand::SetMute(muted)).await?;
+        let state = self.get_state().await;
+        Ok(state.muted)
+    }
+
     async fn get_state(&self) -> PlayerState {
         let (tx, rx) = oneshot::channel();
-        if self.cmd_tx.send(EngineCommand::GetState(tx)).await.is_ok() {
+        if self.send(EngineCommand::GetState(tx)).await.is_ok() {
             rx.await.unwrap_or_default()
         } else {
             PlayerState::default()
@@ -274,6 +827,9 @@ impl AudioEngine for MpvPlayer {
     }
 
     async fn set_audio_device(&self, device_id: Option<String>) -> Result<(), String> {
+        if let Ok(mut config) = self.config.lock() {
+            config.audio_device = device_id.clone();
+        }
         self.send(EngineCommand::SetAudioDevice(device_id)).await
     }
 
@@ -285,6 +841,31 @@ impl AudioEngine for MpvPlayer {
         Ok(state.exclusive)
     }
 
+    async fn set_night_mode(&self, enabled: bool) -> Result<(), String> {
+        if let Ok(mut config) = self.config.lock() {
+            config.night_mode = enabled;
+        }
+        self.send(EngineCommand::SetNightMode(enabled)).await
+    }
+
+    async fn set_prevent_clipping(&self, enabled: bool) -> Result<(), String> {
+        if let Ok(mut config) = self.config.lock() {
+            config.prevent_clipping = enabled;
+        }
+        self.send(EngineCommand::SetPreventClipping(enabled)).await
+    }
+
+    async fn set_track_gain(&self, gain_db: Option<f32>, peak: Option<f32>) -> Result<(), String> {
+        self.send(EngineCommand::SetTrackGain { gain_db, peak })
+            .await
+    }
+
+    async fn get_engine_info(&self) -> Result<EngineInfo, String> {
+        let (tx, rx) = oneshot::channel();
+        self.send(EngineCommand::GetEngineInfo(tx)).await?;
+        rx.await.map_err(|_| "Actor dropped".to_string())
+    }
+
     fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
         self.event_tx.subscribe()
     }
@@ -0,0 +1,127 @@
+use crate::models::entities::PlayerEvent;
+use crate::models::{AudioDevice, EngineInfo, PlayerState};
+use crate::traits::{AudioEngine, AudioStream};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+/// Wraps the active engine so it can be replaced at runtime. Subscribers hold
+/// receivers on this wrapper's channel, which outlives any single engine, so
+/// swapping doesn't cut off the queue, scrobbler or frontend listeners.
+#[derive(Clone)]
+pub struct SwitchableEngine {
+    inner: Arc<RwLock<Box<dyn AudioEngine>>>,
+    event_tx: broadcast::Sender<PlayerEvent>,
+    forwarder: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl SwitchableEngine {
+    pub fn new(engine: Box<dyn AudioEngine>) -> Self {
+        let (event_tx, _) = broadcast::channel(128);
+        let rx = engine.subscribe();
+        let switchable = Self {
+            inner: Arc::new(RwLock::new(engine)),
+            event_tx,
+            forwarder: Arc::new(Mutex::new(None)),
+        };
+        switchable.forward(rx);
+        switchable
+    }
+
+    fn forward(&self, mut rx: broadcast::Receiver<PlayerEvent>) {
+        let tx = self.event_tx.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        let _ = tx.send(event);
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        if let Ok(mut forwarder) = self.forwarder.lock() {
+            if let Some(old) = forwarder.replace(handle) {
+                old.abort();
+            }
+        }
+    }
+
+    /// Installs `engine` and returns the previous one, already stopped.
+    pub async fn swap(&self, engine: Box<dyn AudioEngine>) -> Box<dyn AudioEngine> {
+        let rx = engine.subscribe();
+        let old = {
+            let mut inner = self.inner.write().await;
+            std::mem::replace(&mut *inner, engine)
+        };
+        self.forward(rx);
+        let _ = old.stop().await;
+        old
+    }
+}
+
+#[async_trait]
+impl AudioEngine for SwitchableEngine {
+    async fn load(&self, stream: AudioStream, auto_play: bool) -> Result<(), String> {
+        self.inner.read().await.load(stream, auto_play).await
+    }
+    async fn play(&self) -> Result<(), String> {
+        self.inner.read().await.play().await
+    }
+    async fn pause(&self) -> Result<(), String> {
+        self.inner.read().await.pause().await
+    }
+    async fn stop(&self) -> Result<(), String> {
+        self.inner.read().await.stop().await
+    }
+    async fn seek(&self, seconds: f64) -> Result<(), String> {
+        self.inner.read().await.seek(seconds).await
+    }
+    async fn set_volume(&self, vol: f32) -> Result<(), String> {
+        self.inner.read().await.set_volume(vol).await
+    }
+    async fn toggle_mute(&self, muted: Option<bool>) -> Result<bool, String> {
+        self.inner.read().await.toggle_mute(muted).await
+    }
+
+    async fn get_state(&self) -> PlayerState {
+        self.inner.read().await.get_state().await
+    }
+
+    async fn get_audio_devices(&self) -> Result<Vec<AudioDevice>, String> {
+        self.inner.read().await.get_audio_devices().await
+    }
+    async fn set_audio_device(&self, device_id: Option<String>) -> Result<(), String> {
+        self.inner.read().await.set_audio_device(device_id).await
+    }
+
+    async fn toggle_exclusive_mode(&self, exclusive: Option<bool>) -> Result<bool, String> {
+        self.inner
+            .read()
+            .await
+            .toggle_exclusive_mode(exclusive)
+            .await
+    }
+
+    async fn set_night_mode(&self, enabled: bool) -> Result<(), String> {
+        self.inner.read().await.set_night_mode(enabled).await
+    }
+
+    async fn set_prevent_clipping(&self, enabled: bool) -> Result<(), String> {
+        self.inner.read().await.set_prevent_clipping(enabled).await
+    }
+
+    async fn set_track_gain(&self, gain_db: Option<f32>, peak: Option<f32>) -> Result<(), String> {
+        self.inner.read().await.set_track_gain(gain_db, peak).await
+    }
+
+    async fn get_engine_info(&self) -> Result<EngineInfo, String> {
+        self.inner.read().await.get_engine_info().await
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.event_tx.subscribe()
+    }
+}
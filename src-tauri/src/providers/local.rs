@@ -1,33 +1,69 @@
-use crate::models::entities::{Album, Artist, Genre, Playlist, Track, UnifiedSearchResult};
+use crate::models::entities::{
+    Album, AlbumArtwork, Artist, ArtistFeature, ArtistMergeResult, ArtworkKind, CoverCleanupResult,
+    Genre, LibraryChangeKind, LibraryChanged, LibraryDatabaseReset, Lyrics, LyricsSource, Playlist,
+    ProviderCapabilities, ScanCancelled, ScanRootFailed, SearchType, Track, TrackSort,
+    UnifiedSearchResult,
+};
 use crate::traits::{AudioStream, LibraryProvider};
 use crate::util::lastfm::LastFmClient;
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use chrono::Utc;
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 use jwalk::WalkDir;
+use lofty::file::FileType;
+use lofty::picture::PictureType;
 use lofty::prelude::*;
 use lofty::read_from_path;
+use lofty::tag::{ItemValue, TagType};
 use rayon::prelude::*;
 use sha2::{Digest, Sha256};
 use sqlx::{sqlite::SqlitePool, Row};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::UNIX_EPOCH;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_specta::Event;
 use tokio::sync::mpsc;
 
 const BATCH_SIZE: usize = 200;
+/// Bump together with a new step in `LocalProvider::migrate`.
+const SCHEMA_VERSION: i64 = 5;
 const COVERS_DIR: &str = "covers";
-
-use crate::models::AppConfig;
+const AUDIO_EXTENSIONS: [&str; 9] = [
+    "mp3", "flac", "wav", "m4a", "ogg", "opus", "aac", "alac", "aiff",
+];
+/// Placeholders stored for tracks with no artist/album tag. These never change
+/// so lookups keyed on them keep working; the user-facing label comes from config.
+const UNKNOWN_ARTIST: &str = "Unknown Artist";
+const UNKNOWN_ALBUM: &str = "Unknown Album";
+/// Cover files younger than this are never pruned, since a scan running
+/// alongside the cleanup may have written them before committing the album row.
+const COVER_PRUNE_GRACE: Duration = Duration::from_secs(10 * 60);
+/// Album track order: by disc, numbered tracks first, then title and path for ties.
+const ALBUM_TRACK_ORDER: &str = "COALESCE(t.disc_number, 1) ASC,
+    t.track_number IS NULL ASC,
+    t.track_number ASC,
+    t.title COLLATE NOCASE ASC,
+    t.path ASC";
+const DEFAULT_DISC_SUFFIXES: [&str; 3] = ["disc", "disk", "cd"];
+const NUMBER_WORDS: [&str; 10] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+];
+
+use crate::models::{config::SourceConfig, AppConfig};
 
 pub struct LocalProvider {
     db: SqlitePool,
     id: String,
     data_dir: PathBuf,
     config: AppConfig,
+    app: AppHandle,
+    /// Set by `cancel_scan`; checked by the running scan's walker and consumer.
+    scan_cancel: Arc<AtomicBool>,
 }
 
 impl LocalProvider {
@@ -36,6 +72,7 @@ impl LocalProvider {
         db_path: &Path,
         data_dir: &Path,
         config: AppConfig,
+        app: AppHandle,
     ) -> Result<Self> {
         if !data_dir.exists() {
             fs::create_dir_all(data_dir).context("Failed to create data directory")?;
@@ -56,14 +93,15 @@ impl LocalProvider {
             fs::File::create(db_path).context("Failed to create database file")?;
         }
 
-        let db_url = format!("sqlite://{}", db_path.to_string_lossy());
-        let db = SqlitePool::connect(&db_url).await?;
+        let db = open_checked(db_path, &id, &app).await?;
 
         let provider = Self {
             db,
             id,
             data_dir: data_dir.to_path_buf(),
             config,
+            app,
+            scan_cancel: Arc::new(AtomicBool::new(false)),
         };
 
         provider.init_schema().await?;
@@ -84,7 +122,9 @@ impl LocalProvider {
                 name TEXT NOT NULL,
                 bio TEXT,
                 image_url TEXT,
-                UNIQUE(name)
+                mbid TEXT,
+                sort_tag TEXT,
+                sort_name TEXT
             );
 
             CREATE TABLE IF NOT EXISTS albums (
@@ -93,6 +133,8 @@ impl LocalProvider {
                 artist_id TEXT,
                 cover_art TEXT,
                 year INTEGER,
+                sort_tag TEXT,
+                sort_name TEXT,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 FOREIGN KEY(artist_id) REFERENCES artists(id),
                 UNIQUE(title, artist_id)
@@ -110,9 +152,18 @@ impl LocalProvider {
                 year INTEGER,
                 genre TEXT,
                 bitrate INTEGER,
+                codec TEXT,
+                peak REAL,
                 play_count INTEGER DEFAULT 0,
                 liked BOOLEAN DEFAULT 0,
+                explicit BOOLEAN DEFAULT 0,
+                rating INTEGER,
+                skip_count INTEGER DEFAULT 0,
                 mtime INTEGER DEFAULT 0,
+                media_path TEXT,
+                start_offset REAL,
+                end_offset REAL,
+                gain_db REAL,
                 created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 FOREIGN KEY(artist_id) REFERENCES artists(id),
                 FOREIGN KEY(album_id) REFERENCES albums(id),
@@ -135,6 +186,14 @@ impl LocalProvider {
                 FOREIGN KEY(artist_id) REFERENCES artists(id) ON DELETE CASCADE
             );
             
+            CREATE TABLE IF NOT EXISTS album_artwork (
+                album_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                PRIMARY KEY(album_id, kind),
+                FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
+            );
+
             CREATE TABLE IF NOT EXISTS library_roots (
                 path TEXT PRIMARY KEY
             );
@@ -166,6 +225,148 @@ impl LocalProvider {
             CREATE TABLE IF NOT EXISTS scan_found (
                 path TEXT PRIMARY KEY
             );
+
+            CREATE TABLE IF NOT EXISTS scan_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            "#,
+        )
+        .execute(&self.db)
+        .await?;
+
+        self.migrate().await?;
+        self.refresh_sort_names().await
+    }
+
+    /// Brings an existing database up to `SCHEMA_VERSION`, tracked in SQLite's
+    /// `user_version`. Each step must be idempotent: the version is only bumped
+    /// after a step finishes, so an interrupted step runs again on next start.
+    /// Append new steps; never change ones that have shipped.
+    async fn migrate(&self) -> Result<()> {
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+            .fetch_one(&self.db)
+            .await?;
+        if version > SCHEMA_VERSION {
+            log::warn!(
+                "Library database {} has schema version {}, newer than this build ({})",
+                self.id,
+                version,
+                SCHEMA_VERSION
+            );
+            return Ok(());
+        }
+
+        for target in (version + 1)..=SCHEMA_VERSION {
+            log::info!(
+                "Migrating library database {} to schema version {}",
+                self.id,
+                target
+            );
+            match target {
+                1 => self.migrate_v1().await?,
+                2 => {
+                    self.ensure_column("tracks", "skip_count", "INTEGER DEFAULT 0")
+                        .await?;
+                }
+                3 => {
+                    self.ensure_column("tracks", "media_path", "TEXT").await?;
+                    self.ensure_column("tracks", "start_offset", "REAL").await?;
+                    self.ensure_column("tracks", "end_offset", "REAL").await?;
+                }
+                4 => {
+                    for table in ["artists", "albums"] {
+                        self.ensure_column(table, "sort_tag", "TEXT").await?;
+                        self.ensure_column(table, "sort_name", "TEXT").await?;
+                    }
+                    // Re-read files so sort tags of existing tracks are picked up.
+                    sqlx::query("UPDATE tracks SET mtime = 0")
+                        .execute(&self.db)
+                        .await?;
+                }
+                5 => {
+                    self.ensure_column("tracks", "gain_db", "REAL").await?;
+                }
+                _ => unreachable!("missing migration for schema version {}", target),
+            }
+            sqlx::query(&format!("PRAGMA user_version = {}", target))
+                .execute(&self.db)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Columns added before versioning existed. Databases created by any older
+    /// build may be missing some of them.
+    async fn migrate_v1(&self) -> Result<()> {
+        self.migrate_artists_mbid().await?;
+        self.ensure_column("tracks", "explicit", "BOOLEAN DEFAULT 0")
+            .await?;
+        self.ensure_column("tracks", "rating", "INTEGER").await?;
+        self.ensure_column("tracks", "mtime", "INTEGER DEFAULT 0")
+            .await?;
+        let added_codec = self.ensure_column("tracks", "codec", "TEXT").await?;
+        let added_peak = self.ensure_column("tracks", "peak", "REAL").await?;
+        if added_codec || added_peak {
+            // Force the next scan to re-read existing files so new columns get filled in.
+            sqlx::query("UPDATE tracks SET mtime = 0")
+                .execute(&self.db)
+                .await?;
+        }
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_tracks_path_mtime ON tracks(path, mtime)")
+            .execute(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Older databases declared `UNIQUE(name)` on artists, which makes two artists
+    /// sharing a name impossible. Rebuild the table without it so rows can be told
+    /// apart by MusicBrainz id, following SQLite's documented table-rebuild steps.
+    async fn migrate_artists_mbid(&self) -> Result<()> {
+        let table_sql: String = sqlx::query_scalar(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'artists'",
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        if table_sql.contains("UNIQUE(name)") {
+            log::info!("Migrating artists table to support MusicBrainz ids");
+            let mut conn = self.db.acquire().await?;
+            sqlx::query("PRAGMA foreign_keys = OFF")
+                .execute(&mut *conn)
+                .await?;
+            let res = sqlx::query(
+                r#"
+                BEGIN;
+                CREATE TABLE artists_new (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    bio TEXT,
+                    image_url TEXT,
+                    mbid TEXT
+                );
+                INSERT INTO artists_new (id, name, bio, image_url)
+                    SELECT id, name, bio, image_url FROM artists;
+                DROP TABLE artists;
+                ALTER TABLE artists_new RENAME TO artists;
+                COMMIT;
+                "#,
+            )
+            .execute(&mut *conn)
+            .await;
+            if res.is_err() {
+                let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+            }
+            sqlx::query("PRAGMA foreign_keys = ON")
+                .execute(&mut *conn)
+                .await?;
+            res?;
+        }
+
+        sqlx::query(
+            r#"
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_artists_mbid ON artists(mbid) WHERE mbid IS NOT NULL;
+            CREATE INDEX IF NOT EXISTS idx_artists_name ON artists(name COLLATE NOCASE);
             "#,
         )
         .execute(&self.db)
@@ -173,14 +374,725 @@ impl LocalProvider {
         Ok(())
     }
 
+    /// Repoints everything owned by `merge_id` to `keep_id` and deletes `merge_id`.
+    /// Albums whose title already exists under `keep_id` are folded into that album,
+    /// since `UNIQUE(title, artist_id)` would otherwise reject the update.
+    async fn merge_artists_tx(&self, keep_id: &str, merge_id: &str) -> Result<ArtistMergeResult> {
+        if keep_id == merge_id {
+            return Err(anyhow!("Cannot merge an artist into itself"));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let found: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM artists WHERE id IN (?, ?)")
+            .bind(keep_id)
+            .bind(merge_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if found != 2 {
+            return Err(anyhow!("Artist not found"));
+        }
+
+        let mut result = ArtistMergeResult::default();
+
+        let duplicates: Vec<(String, String)> = sqlx::query_as(
+            r#"SELECT m.id, k.id FROM albums m
+            JOIN albums k ON k.title = m.title AND k.artist_id = ?
+            WHERE m.artist_id = ?"#,
+        )
+        .bind(keep_id)
+        .bind(merge_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for (merge_album, keep_album) in &duplicates {
+            sqlx::query("UPDATE tracks SET album_id = ? WHERE album_id = ?")
+                .bind(keep_album)
+                .bind(merge_album)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "INSERT OR IGNORE INTO album_artists (album_id, artist_id) SELECT ?, artist_id FROM album_artists WHERE album_id = ?",
+            )
+            .bind(keep_album)
+            .bind(merge_album)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM album_artists WHERE album_id = ?")
+                .bind(merge_album)
+                .execute(&mut *tx)
+                .await?;
+            move_album_artwork(&mut tx, merge_album, keep_album).await?;
+            sqlx::query("DELETE FROM albums WHERE id = ?")
+                .bind(merge_album)
+                .execute(&mut *tx)
+                .await?;
+        }
+        result.albums_merged = duplicates.len() as u32;
+
+        result.albums_updated = sqlx::query("UPDATE albums SET artist_id = ? WHERE artist_id = ?")
+            .bind(keep_id)
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as u32;
+
+        result.tracks_updated = sqlx::query("UPDATE tracks SET artist_id = ? WHERE artist_id = ?")
+            .bind(keep_id)
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as u32;
+
+        // Rows already linked to both artists collide on the primary key; those are
+        // skipped here and removed with the leftovers below.
+        result.track_artists_updated =
+            sqlx::query("UPDATE OR IGNORE track_artists SET artist_id = ? WHERE artist_id = ?")
+                .bind(keep_id)
+                .bind(merge_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected() as u32;
+        sqlx::query("DELETE FROM track_artists WHERE artist_id = ?")
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?;
+
+        result.album_artists_updated =
+            sqlx::query("UPDATE OR IGNORE album_artists SET artist_id = ? WHERE artist_id = ?")
+                .bind(keep_id)
+                .bind(merge_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected() as u32;
+        sqlx::query("DELETE FROM album_artists WHERE artist_id = ?")
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM artists WHERE id = ?")
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Moves every track of `merge_id` into `keep_id` and deletes `merge_id`.
+    /// Returns the number of tracks moved.
+    async fn merge_albums_tx(&self, keep_id: &str, merge_id: &str) -> Result<u32> {
+        if keep_id == merge_id {
+            return Err(anyhow!("Cannot merge an album into itself"));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let found: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM albums WHERE id IN (?, ?)")
+            .bind(keep_id)
+            .bind(merge_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if found != 2 {
+            return Err(anyhow!("Album not found"));
+        }
+
+        let moved = sqlx::query("UPDATE tracks SET album_id = ? WHERE album_id = ?")
+            .bind(keep_id)
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as u32;
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO album_artists (album_id, artist_id) SELECT ?, artist_id FROM album_artists WHERE album_id = ?",
+        )
+        .bind(keep_id)
+        .bind(merge_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM album_artists WHERE album_id = ?")
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?;
+        move_album_artwork(&mut tx, merge_id, keep_id).await?;
+
+        sqlx::query(
+            r#"UPDATE albums SET
+            cover_art = COALESCE(cover_art, (SELECT cover_art FROM albums WHERE id = ?2)),
+            year = COALESCE(year, (SELECT year FROM albums WHERE id = ?2))
+            WHERE id = ?1"#,
+        )
+        .bind(keep_id)
+        .bind(merge_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM albums WHERE id = ?")
+            .bind(merge_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(moved)
+    }
+
+    /// Moves `track_ids` out of `album_id` into a new album titled `new_title` by the
+    /// same artist. The source album is removed if no tracks are left in it.
+    async fn split_album_tx(
+        &self,
+        album_id: &str,
+        track_ids: &[String],
+        new_title: &str,
+    ) -> Result<String> {
+        let new_title = new_title.trim();
+        if new_title.is_empty() {
+            return Err(anyhow!("Album title cannot be empty"));
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let (artist_id, cover_art, year): (Option<String>, Option<String>, Option<i64>) =
+            sqlx::query_as("SELECT artist_id, cover_art, year FROM albums WHERE id = ?")
+                .bind(album_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or_else(|| anyhow!("Album not found"))?;
+
+        let new_id = uuid::Uuid::new_v4().to_string();
+        let inserted = sqlx::query(
+            "INSERT OR IGNORE INTO albums (id, title, artist_id, cover_art, year) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&new_id)
+        .bind(new_title)
+        .bind(&artist_id)
+        .bind(&cover_art)
+        .bind(year)
+        .execute(&mut *tx)
+        .await?;
+        if inserted.rows_affected() == 0 {
+            return Err(anyhow!(
+                "An album titled \"{}\" already exists for this artist",
+                new_title
+            ));
+        }
+
+        sqlx::query(
+            "INSERT INTO album_artists (album_id, artist_id) SELECT ?, artist_id FROM album_artists WHERE album_id = ?",
+        )
+        .bind(&new_id)
+        .bind(album_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO album_artwork (album_id, kind, filename) SELECT ?, kind, filename FROM album_artwork WHERE album_id = ?",
+        )
+        .bind(&new_id)
+        .bind(album_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut moved = 0;
+        for track_id in track_ids {
+            moved += sqlx::query("UPDATE tracks SET album_id = ? WHERE id = ? AND album_id = ?")
+                .bind(&new_id)
+                .bind(track_id)
+                .bind(album_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        if moved == 0 {
+            return Err(anyhow!("None of the tracks belong to this album"));
+        }
+
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tracks WHERE album_id = ?")
+            .bind(album_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if remaining == 0 {
+            sqlx::query("DELETE FROM album_artists WHERE album_id = ?")
+                .bind(album_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM album_artwork WHERE album_id = ?")
+                .bind(album_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM albums WHERE id = ?")
+                .bind(album_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(new_id)
+    }
+
+    /// Deletes every track under `prefix` and the albums and artists left with
+    /// nothing pointing at them. Returns how many tracks were removed.
+    async fn remove_tracks_under_path_tx(&self, prefix: &str) -> Result<u32> {
+        let prefix = dir_prefix(prefix);
+        let mut tx = self.db.begin().await?;
+        let removed = sqlx::query("DELETE FROM tracks WHERE path >= ? AND path < ?")
+            .bind(&prefix)
+            .bind(format!("{}\u{10FFFF}", prefix))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected() as u32;
+
+        if removed > 0 {
+            sqlx::query(
+                "DELETE FROM albums WHERE NOT EXISTS (SELECT 1 FROM tracks WHERE tracks.album_id = albums.id)",
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                r#"DELETE FROM artists WHERE
+                    NOT EXISTS (SELECT 1 FROM tracks WHERE tracks.artist_id = artists.id)
+                    AND NOT EXISTS (SELECT 1 FROM track_artists WHERE track_artists.artist_id = artists.id)
+                    AND NOT EXISTS (SELECT 1 FROM albums WHERE albums.artist_id = artists.id)
+                    AND NOT EXISTS (SELECT 1 FROM album_artists WHERE album_artists.artist_id = artists.id)"#,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    /// Adds `column` to `table` if it is missing. Returns whether the column was added.
+    async fn ensure_column(&self, table: &str, column: &str, definition: &str) -> Result<bool> {
+        let columns: Vec<String> =
+            sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+                .fetch_all(&self.db)
+                .await?;
+        if !columns.iter().any(|c| c == column) {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .execute(&self.db)
+            .await?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Recomputes the `sort_name` listings order by: the sort tag when the files
+    /// have one, otherwise the name without a leading article. Cleared when sort
+    /// names are turned off, so listings fall back to the plain name.
+    async fn refresh_sort_names(&self) -> Result<()> {
+        for (table, column) in [("artists", "name"), ("albums", "title")] {
+            let rows: Vec<(String, String, Option<String>, Option<String>)> = sqlx::query_as(
+                &format!("SELECT id, {}, sort_tag, sort_name FROM {}", column, table),
+            )
+            .fetch_all(&self.db)
+            .await?;
+
+            let update = format!("UPDATE {} SET sort_name = ? WHERE id = ?", table);
+            let mut tx = self.db.begin().await?;
+            for (id, name, tag, current) in rows {
+                let sort_name = self.config.use_sort_names.then(|| {
+                    tag.unwrap_or_else(|| {
+                        strip_article(&name, &self.config.sort_articles).to_string()
+                    })
+                });
+                if sort_name != current {
+                    sqlx::query(&update)
+                        .bind(sort_name)
+                        .bind(&id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+
+    fn follow_symlinks(&self) -> bool {
+        self.config.sources.iter().any(|s| match s {
+            SourceConfig::Local {
+                id,
+                follow_symlinks,
+                ..
+            } => id == &self.id && *follow_symlinks,
+            _ => false,
+        })
+    }
+
+    fn artist_label(&self, name: String) -> String {
+        if name == UNKNOWN_ARTIST {
+            self.config.unknown_artist_label.clone()
+        } else {
+            name
+        }
+    }
+
+    fn album_label(&self, title: String) -> String {
+        if title == UNKNOWN_ALBUM {
+            self.config.unknown_album_label.clone()
+        } else {
+            title
+        }
+    }
+
+    fn map_track(&self, row: sqlx::sqlite::SqliteRow) -> Track {
+        let mut track = map_row_to_track(row, Some(self.id.clone()));
+        track.artist_name = self.artist_label(track.artist_name);
+        track.album_title = self.album_label(track.album_title);
+        track
+    }
+
+    fn map_album(&self, row: sqlx::sqlite::SqliteRow) -> Album {
+        let mut album = map_row_to_album(row);
+        album.title = self.album_label(album.title);
+        album.artist_name = self.artist_label(album.artist_name);
+        album
+    }
+
+    fn filename_pattern(&self) -> Option<String> {
+        self.config.sources.iter().find_map(|s| match s {
+            SourceConfig::Local {
+                id,
+                filename_pattern,
+                ..
+            } if id == &self.id => filename_pattern.clone(),
+            _ => None,
+        })
+    }
+
+    fn tag_charset(&self) -> Option<TagCharset> {
+        self.config.sources.iter().find_map(|s| match s {
+            SourceConfig::Local {
+                id,
+                detect_tag_encoding,
+                tag_encoding,
+                ..
+            } if id == &self.id => {
+                if let Some(label) = tag_encoding {
+                    match encoding_rs::Encoding::for_label(label.trim().as_bytes()) {
+                        Some(encoding) => return Some(TagCharset::Fixed(encoding)),
+                        None => log::warn!("Unknown tag encoding '{}', ignoring", label),
+                    }
+                }
+                detect_tag_encoding.then_some(TagCharset::Detect)
+            }
+            _ => None,
+        })
+    }
+
+    /// Points albums whose cover file is missing back at a cover re-extracted
+    /// from one of their tracks, or clears it so enrichment can fill it again.
+    /// Returns (relinked, cleared).
+    async fn relink_missing_covers(&self) -> Result<(u32, u32)> {
+        let covers_dir = self.data_dir.join(COVERS_DIR);
+        let albums: Vec<(String, String, Option<String>)> = sqlx::query_as(
+            r#"SELECT al.id, al.cover_art,
+                (SELECT COALESCE(media_path, path) FROM tracks WHERE album_id = al.id LIMIT 1)
+            FROM albums al
+            WHERE al.cover_art IS NOT NULL AND al.cover_art NOT LIKE 'http%'"#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let (mut relinked, mut cleared) = (0, 0);
+        for (album_id, cover, track_path) in albums {
+            if covers_dir.join(&cover).exists() {
+                continue;
+            }
+            let dir = covers_dir.clone();
+            let replacement = match track_path {
+                Some(path) => tokio::task::spawn_blocking(move || {
+                    let meta = parse_metadata(Path::new(&path), None, None).ok()?;
+                    save_cover_art(&dir, meta.cover_image.as_ref()?).ok()
+                })
+                .await
+                .unwrap_or(None),
+                None => None,
+            };
+            sqlx::query("UPDATE albums SET cover_art = ? WHERE id = ?")
+                .bind(&replacement)
+                .bind(&album_id)
+                .execute(&self.db)
+                .await?;
+            if replacement.is_some() {
+                relinked += 1;
+            } else {
+                cleared += 1;
+            }
+        }
+
+        // Extra artwork and playlist covers have fallbacks, so dangling ones are dropped.
+        let artwork: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT album_id, kind, filename FROM album_artwork")
+                .fetch_all(&self.db)
+                .await?;
+        for (album_id, kind, filename) in artwork {
+            if !covers_dir.join(&filename).exists() {
+                sqlx::query("DELETE FROM album_artwork WHERE album_id = ? AND kind = ?")
+                    .bind(album_id)
+                    .bind(kind)
+                    .execute(&self.db)
+                    .await?;
+            }
+        }
+        let playlists: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, cover_art FROM playlists WHERE cover_art IS NOT NULL")
+                .fetch_all(&self.db)
+                .await?;
+        for (id, filename) in playlists {
+            if !covers_dir.join(&filename).exists() {
+                sqlx::query("UPDATE playlists SET cover_art = NULL WHERE id = ?")
+                    .bind(id)
+                    .execute(&self.db)
+                    .await?;
+            }
+        }
+        Ok((relinked, cleared))
+    }
+
+    /// Deletes cover files no local library references. The covers directory
+    /// is shared by every local source, so databases of sources that aren't
+    /// loaded right now are consulted too. Returns (files removed, bytes freed).
+    async fn prune_unreferenced_covers(&self) -> Result<(u32, u64)> {
+        let mut referenced = referenced_covers(&self.db).await?;
+        for entry in fs::read_dir(&self.data_dir)?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with("library_") || !name.ends_with(".db") {
+                continue;
+            }
+            if name == format!("library_{}.db", self.id) {
+                continue;
+            }
+            let url = format!("sqlite://{}?mode=ro", entry.path().to_string_lossy());
+            let pool = SqlitePool::connect(&url)
+                .await
+                .with_context(|| format!("Failed to open {}", name))?;
+            let res = referenced_covers(&pool).await;
+            pool.close().await;
+            referenced.extend(res?);
+        }
+
+        let (mut removed, mut bytes) = (0, 0);
+        for entry in fs::read_dir(self.data_dir.join(COVERS_DIR))?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if referenced.contains(&name) {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let fresh = meta
+                .modified()
+                .ok()
+                .and_then(|m| m.elapsed().ok())
+                .is_none_or(|age| age < COVER_PRUNE_GRACE);
+            if !meta.is_file() || fresh {
+                continue;
+            }
+            if fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+                bytes += meta.len();
+            }
+        }
+        Ok((removed, bytes))
+    }
+
+    fn disc_suffixes(&self) -> Vec<String> {
+        self.config
+            .sources
+            .iter()
+            .find_map(|s| match s {
+                SourceConfig::Local {
+                    id, disc_suffixes, ..
+                } if id == &self.id => disc_suffixes.clone(),
+                _ => None,
+            })
+            .unwrap_or_else(|| DEFAULT_DISC_SUFFIXES.map(String::from).to_vec())
+    }
+
+    /// Folds albums split by a disc suffix ("Album (Disc 1)", "Album CD2") into
+    /// one album under the bare title, filling in missing disc numbers from the
+    /// suffix. Catches libraries scanned before suffixes were stripped at scan
+    /// time. Returns how many albums were merged away.
+    async fn merge_disc_split_albums(&self) -> Result<u32> {
+        let keywords = self.disc_suffixes();
+        if keywords.is_empty() {
+            return Ok(0);
+        }
+
+        let albums: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT id, title, artist_id FROM albums")
+                .fetch_all(&self.db)
+                .await?;
+        let mut by_title: HashMap<(String, String), String> = albums
+            .iter()
+            .map(|(id, title, artist)| ((artist.clone(), title.to_lowercase()), id.clone()))
+            .collect();
+
+        let mut merged = 0;
+        for (id, title, artist_id) in &albums {
+            let Some((base, disc)) = strip_disc_suffix(title, &keywords) else {
+                continue;
+            };
+            sqlx::query(
+                "UPDATE tracks SET disc_number = ? WHERE album_id = ? AND disc_number IS NULL",
+            )
+            .bind(disc)
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+
+            let key = (artist_id.clone(), base.to_lowercase());
+            match by_title.get(&key) {
+                Some(keep_id) => {
+                    self.merge_albums_tx(keep_id, id).await?;
+                    merged += 1;
+                }
+                None => {
+                    sqlx::query("UPDATE albums SET title = ? WHERE id = ?")
+                        .bind(&base)
+                        .bind(id)
+                        .execute(&self.db)
+                        .await?;
+                    by_title.insert(key, id.clone());
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Loads stored mtimes for the tracks under one root. Rows are streamed
+    /// straight into the map over the (path, mtime) index, and only one root's
+    /// worth is held at a time, which keeps huge libraries from stalling here.
+    async fn existing_mtimes(&self, root: &str) -> Result<HashMap<PathBuf, i64>> {
+        let started = Instant::now();
+        // Range over the path index rather than LIKE, which SQLite can't use an
+        // index for on a case-sensitive column.
+        let mut rows = sqlx::query_as::<_, (String, Option<i64>)>(
+            "SELECT path, mtime FROM tracks WHERE path >= ? AND path < ?",
+        )
+        .bind(root)
+        .bind(format!("{}\u{10FFFF}", root))
+        .fetch(&self.db);
+
+        let mut existing = HashMap::new();
+        while let Some((path, mtime)) = rows.try_next().await? {
+            existing.insert(PathBuf::from(path), mtime.unwrap_or(0));
+        }
+        log::info!(
+            "Loaded {} known tracks under {} in {:?}",
+            existing.len(),
+            root,
+            started.elapsed()
+        );
+        Ok(existing)
+    }
+
+    /// Tags already read with a different charset setting are stale, so files are
+    /// re-read on the next scan whenever the setting changes.
+    async fn reset_mtimes_if_charset_changed(&self) -> Result<()> {
+        let setting = TagCharset::setting(self.tag_charset().as_ref());
+        let stored: Option<String> =
+            sqlx::query_scalar("SELECT value FROM scan_settings WHERE key = 'tag_charset'")
+                .fetch_optional(&self.db)
+                .await?;
+        if stored.as_deref().unwrap_or("") == setting {
+            return Ok(());
+        }
+
+        log::info!(
+            "Tag charset setting of {} changed; re-reading all files",
+            self.id
+        );
+        let mut tx = self.db.begin().await?;
+        sqlx::query("UPDATE tracks SET mtime = 0")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR REPLACE INTO scan_settings (key, value) VALUES ('tag_charset', ?)")
+            .bind(setting)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// With symlinks followed, one file can be reached under several paths. Returns
+    /// every alias but one per file, keeping the path already in the library or
+    /// else the smallest, so repeated scans keep recording the same path.
+    async fn symlink_aliases(&self, roots: Vec<String>) -> Result<HashSet<PathBuf>> {
+        if !self.follow_symlinks() {
+            return Ok(HashSet::new());
+        }
+
+        let cancel = self.scan_cancel.clone();
+        let groups = tokio::task::spawn_blocking(move || {
+            let mut by_target: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+            for root in &roots {
+                for entry in WalkDir::new(root).follow_links(true).into_iter().flatten() {
+                    if cancel.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let path = entry.path();
+                    let is_audio = path.extension().is_some_and(|ext| {
+                        AUDIO_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+                    });
+                    if !entry.file_type().is_file() || !is_audio {
+                        continue;
+                    }
+                    if let Ok(target) = fs::canonicalize(&path) {
+                        by_target.entry(target).or_default().push(path);
+                    }
+                }
+            }
+            by_target
+                .into_values()
+                .filter_map(|mut aliases| {
+                    aliases.sort();
+                    aliases.dedup();
+                    (aliases.len() > 1).then_some(aliases)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await?;
+
+        let mut skipped = HashSet::new();
+        for mut aliases in groups {
+            let mut keep = 0;
+            for (i, alias) in aliases.iter().enumerate() {
+                let known: Option<i64> = sqlx::query_scalar("SELECT 1 FROM tracks WHERE path = ?")
+                    .bind(alias.to_string_lossy().to_string())
+                    .fetch_optional(&self.db)
+                    .await?;
+                if known.is_some() {
+                    keep = i;
+                    break;
+                }
+            }
+            aliases.swap_remove(keep);
+            skipped.extend(aliases);
+        }
+        Ok(skipped)
+    }
+
     async fn scan_path(
         &self,
         root_path: &str,
         existing_map: Arc<HashMap<PathBuf, i64>>,
+        skipped_aliases: Arc<HashSet<PathBuf>>,
     ) -> Result<()> {
         let root = root_path.to_string();
+        let follow_links = self.follow_symlinks();
+        let filename_pattern = self.filename_pattern();
+        let tag_charset = self.tag_charset();
+        let disc_suffixes = self.disc_suffixes();
         let db = self.db.clone();
         let covers_dir = self.data_dir.join(COVERS_DIR);
+        let cancel = self.scan_cancel.clone();
+        let consumer_cancel = cancel.clone();
+        let cue_index = CueIndex::default();
 
         enum ScanResult {
             Found(PathBuf),
@@ -200,6 +1112,9 @@ impl LocalProvider {
             let mut processed_count = 0;
 
             while let Some(res) = rx.recv().await {
+                if consumer_cancel.load(Ordering::Relaxed) {
+                    break;
+                }
                 processed_count += 1;
 
                 match res {
@@ -211,13 +1126,24 @@ impl LocalProvider {
                     }
                     ScanResult::New(path, meta, mtime) => {
                         let mut track_artist_ids = Vec::new();
-                        for artist_name in &meta.artists {
-                            if let Some(id) = artist_cache.get(artist_name) {
+                        for (i, artist_name) in meta.artists.iter().enumerate() {
+                            let mbid = meta.artist_mbids.get(i).map(String::as_str);
+                            let cache_key = mbid.unwrap_or(artist_name).to_string();
+                            if let Some(id) = artist_cache.get(&cache_key) {
                                 track_artist_ids.push(id.clone());
                             } else {
-                                match resolve_artist_single(&db, artist_name).await {
+                                match resolve_artist_single(&db, artist_name, mbid).await {
                                     Ok(id) => {
-                                        artist_cache.insert(artist_name.clone(), id.clone());
+                                        // The sort tag covers the whole artist
+                                        // field, so only trust it for a single artist.
+                                        let sort = meta
+                                            .artist_sort
+                                            .as_ref()
+                                            .filter(|_| meta.artists.len() == 1);
+                                        if let Some(sort) = sort {
+                                            let _ = set_sort_tag(&db, "artists", &id, sort).await;
+                                        }
+                                        artist_cache.insert(cache_key, id.clone());
                                         track_artist_ids.push(id);
                                     }
                                     Err(e) => {
@@ -232,8 +1158,8 @@ impl LocalProvider {
                         }
 
                         if track_artist_ids.is_empty() {
-                            let unknown_name = "Unknown Artist".to_string();
-                            if let Ok(id) = resolve_artist_single(&db, &unknown_name).await {
+                            let unknown_name = UNKNOWN_ARTIST.to_string();
+                            if let Ok(id) = resolve_artist_single(&db, &unknown_name, None).await {
                                 track_artist_ids.push(id);
                             }
                         }
@@ -243,9 +1169,25 @@ impl LocalProvider {
 
                         let album_artist_name =
                             meta.album_artist.as_ref().unwrap_or(&meta.artists[0]);
+                        let album_artist_mbid = match &meta.album_artist {
+                            Some(_) => meta.album_artist_mbid.as_deref(),
+                            None => meta.artist_mbids.first().map(String::as_str),
+                        };
+                        let album_artist_sort = match &meta.album_artist {
+                            Some(_) => meta.album_artist_sort.as_ref(),
+                            None if meta.artists.len() == 1 => meta.artist_sort.as_ref(),
+                            None => None,
+                        };
                         let album_artist_id =
-                            match resolve_artist_single(&db, album_artist_name).await {
-                                Ok(id) => id,
+                            match resolve_artist_single(&db, album_artist_name, album_artist_mbid)
+                                .await
+                            {
+                                Ok(id) => {
+                                    if let Some(sort) = album_artist_sort {
+                                        let _ = set_sort_tag(&db, "artists", &id, sort).await;
+                                    }
+                                    id
+                                }
                                 Err(_) => primary_artist_id.clone(),
                             };
                         let album_key = format!("{}::{}", album_artist_id, meta.album);
@@ -259,11 +1201,15 @@ impl LocalProvider {
                                 &album_artist_id,
                                 &track_artist_ids,
                                 &meta.cover_image,
+                                &meta.artwork,
                                 &covers_dir,
                             )
                             .await
                             {
                                 Ok(id) => {
+                                    if let Some(sort) = &meta.album_sort {
+                                        let _ = set_sort_tag(&db, "albums", &id, sort).await;
+                                    }
                                     album_cache.insert(album_key.clone(), id.clone());
                                     id
                                 }
@@ -298,27 +1244,99 @@ impl LocalProvider {
         });
 
         tokio::task::spawn_blocking(move || {
-            let walker = WalkDir::new(&root).follow_links(true).into_iter();
+            let walker = WalkDir::new(&root)
+                .follow_links(follow_links)
+                .into_iter()
+                .take_while(|_| !cancel.load(Ordering::Relaxed));
 
             walker.par_bridge().for_each(|entry_res| match entry_res {
                 Ok(entry) => {
-                    if entry.file_type().is_file() {
+                    if entry.file_type().is_file() && !cancel.load(Ordering::Relaxed) {
                         let path = entry.path();
                         if let Some(ext) = path.extension() {
                             let ext_str = ext.to_string_lossy().to_lowercase();
-                            if [
-                                "mp3", "flac", "wav", "m4a", "ogg", "opus", "aac", "alac", "aiff",
-                            ]
-                            .contains(&ext_str.as_str())
-                            {
+                            if ext_str == "cue" {
+                                let cue_mtime = entry
+                                    .metadata()
+                                    .ok()
+                                    .and_then(|m| m.modified().ok())
+                                    .map(unix_secs)
+                                    .unwrap_or(0);
+                                let sheet = match read_cue_sheet(&path, tag_charset.as_ref()) {
+                                    Ok(sheet) => sheet,
+                                    Err(e) => {
+                                        log::warn!("Skipping {}: {}", path.display(), e);
+                                        return;
+                                    }
+                                };
+                                for file in &sheet.files {
+                                    let Some(audio_mtime) = fs::metadata(&file.path)
+                                        .and_then(|m| m.modified())
+                                        .ok()
+                                        .map(unix_secs)
+                                    else {
+                                        log::warn!(
+                                            "{} references missing file {}",
+                                            path.display(),
+                                            file.path.display()
+                                        );
+                                        continue;
+                                    };
+                                    // Either file changing means the tracks need re-reading.
+                                    let mtime = cue_mtime.max(audio_mtime);
+                                    let track_paths: Vec<PathBuf> = file
+                                        .tracks
+                                        .iter()
+                                        .map(|t| cue_track_path(&path, t.number))
+                                        .collect();
+                                    if track_paths
+                                        .iter()
+                                        .all(|p| existing_map.get(p) == Some(&mtime))
+                                    {
+                                        for p in track_paths {
+                                            let _ = tx.blocking_send(ScanResult::Found(p));
+                                        }
+                                        continue;
+                                    }
+                                    match parse_metadata(
+                                        &file.path,
+                                        filename_pattern.as_deref(),
+                                        tag_charset.as_ref(),
+                                    ) {
+                                        Ok(audio) => {
+                                            for (track_path, mut meta) in
+                                                cue_tracks(&path, &sheet, file, audio)
+                                            {
+                                                if let Some((album, disc)) =
+                                                    strip_disc_suffix(&meta.album, &disc_suffixes)
+                                                {
+                                                    meta.album = album;
+                                                    meta.disc_number.get_or_insert(disc);
+                                                }
+                                                let _ = tx.blocking_send(ScanResult::New(
+                                                    track_path, meta, mtime,
+                                                ));
+                                            }
+                                        }
+                                        Err(e) => {
+                                            log::warn!("Skipping {}: {}", file.path.display(), e);
+                                        }
+                                    }
+                                }
+                                return;
+                            }
+                            if AUDIO_EXTENSIONS.contains(&ext_str.as_str()) {
+                                if cue_index.covers(&path, tag_charset.as_ref())
+                                    || skipped_aliases.contains(&path)
+                                {
+                                    return;
+                                }
+
                                 let mtime = entry
                                     .metadata()
                                     .ok()
                                     .and_then(|m| m.modified().ok())
-                                    .map(|t| {
-                                        t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
-                                            as i64
-                                    })
+                                    .map(unix_secs)
                                     .unwrap_or(0);
 
                                 if let Some(existing_mtime) = existing_map.get(&path) {
@@ -329,8 +1347,18 @@ impl LocalProvider {
                                     }
                                 }
 
-                                match parse_metadata(&path) {
-                                    Ok(meta) => {
+                                match parse_metadata(
+                                    &path,
+                                    filename_pattern.as_deref(),
+                                    tag_charset.as_ref(),
+                                ) {
+                                    Ok(mut meta) => {
+                                        if let Some((album, disc)) =
+                                            strip_disc_suffix(&meta.album, &disc_suffixes)
+                                        {
+                                            meta.album = album;
+                                            meta.disc_number.get_or_insert(disc);
+                                        }
                                         if tx
                                             .blocking_send(ScanResult::New(
                                                 path.to_path_buf(),
@@ -353,42 +1381,214 @@ impl LocalProvider {
                 }
             });
         })
-        .await?;
+        .await?;
+
+        consumer_handle.await.context("Consumer task failed")?;
+        Ok(())
+    }
+}
+
+async fn enrich_albums(db: &SqlitePool, client: Arc<LastFmClient>) -> Result<()> {
+    sqlx::query(
+        r#"UPDATE albums SET year = (
+            SELECT MAX(year) FROM tracks WHERE album_id = albums.id AND year IS NOT NULL
+        ) WHERE year IS NULL"#,
+    )
+    .execute(db)
+    .await?;
+
+    let albums: Vec<(String, String, String)> = sqlx::query_as(
+        r#"SELECT al.id, al.title, ar.name
+        FROM albums al
+        JOIN artists ar ON al.artist_id = ar.id
+        WHERE al.cover_art IS NULL OR al.year IS NULL"#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    futures::stream::iter(albums)
+        .map(|(id, title, artist)| {
+            let client = client.clone();
+            async move {
+                if title == UNKNOWN_ALBUM || artist == UNKNOWN_ARTIST {
+                    return;
+                }
+
+                let mut attempts = 0;
+                loop {
+                    match client.get_album_info(&artist, &title).await {
+                        Ok(info) => {
+                            let cover_art = info.image.and_then(|images| {
+                                images
+                                    .iter()
+                                    .find(|i| i.size == "mega")
+                                    .or(images.last())
+                                    .filter(|i| !i.url.is_empty())
+                                    .map(|i| i.url.clone())
+                            });
+                            let year = info.releasedate.as_deref().and_then(parse_release_year);
+
+                            if cover_art.is_some() || year.is_some() {
+                                let _ = sqlx::query("UPDATE albums SET cover_art = COALESCE(cover_art, ?), year = COALESCE(year, ?) WHERE id = ?")
+                                    .bind(cover_art)
+                                    .bind(year)
+                                    .bind(&id)
+                                    .execute(db)
+                                    .await;
+                            }
+                            break;
+                        }
+                        Err(e) => {
+                            let err_str = e.to_string();
+                            if err_str.contains("429") {
+                                log::warn!("Last.fm Rate Limit (429) for {} - {}. Waiting...", artist, title);
+                                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                                continue;
+                            }
+
+                            attempts += 1;
+                            if attempts >= 3 {
+                                log::warn!(
+                                    "Failed to fetch Last.fm album info for {} - {} after 3 attempts: {}",
+                                    artist,
+                                    title,
+                                    e
+                                );
+                                break;
+                            }
+                            tokio::time::sleep(tokio::time::Duration::from_millis(500 * attempts as u64)).await;
+                        }
+                    }
+                }
+            }
+        })
+        .buffer_unordered(20)
+        .collect::<Vec<()>>()
+        .await;
 
-        consumer_handle.await.context("Consumer task failed")?;
-        Ok(())
+    Ok(())
+}
+
+fn parse_release_year(date: &str) -> Option<u16> {
+    date.split(|c: char| !c.is_ascii_digit())
+        .filter(|part| part.len() == 4)
+        .find_map(|part| part.parse::<u16>().ok())
+        .filter(|y| *y > 1800)
+}
+
+/// `name` without the first of `articles` it starts with, ignoring case. Names
+/// that are nothing but an article are left alone.
+fn strip_article<'a>(name: &'a str, articles: &[String]) -> &'a str {
+    for article in articles {
+        let article = article.trim();
+        if article.is_empty() {
+            continue;
+        }
+        let Some(prefix) = name.get(..article.len()) else {
+            continue;
+        };
+        if !prefix.eq_ignore_ascii_case(article) {
+            continue;
+        }
+        let rest = &name[article.len()..];
+        if !article.ends_with('\'') && !rest.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let rest = rest.trim_start();
+        if !rest.is_empty() {
+            return rest;
+        }
     }
+    name
 }
 
-async fn resolve_artist_single(db: &SqlitePool, name: &str) -> Result<String> {
+/// Records the ARTISTSORT/ALBUMSORT value for an artist or album row. The
+/// effective `sort_name` is derived from it in `refresh_sort_names`.
+async fn set_sort_tag(db: &SqlitePool, table: &str, id: &str, sort_tag: &str) -> Result<()> {
+    sqlx::query(&format!(
+        "UPDATE {} SET sort_tag = ? WHERE id = ? AND sort_tag IS NOT ?",
+        table
+    ))
+    .bind(sort_tag)
+    .bind(id)
+    .bind(sort_tag)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Resolves an artist row, keyed on MusicBrainz id when the tags provide one so
+/// different artists sharing a name stay separate. Without an id, falls back to a
+/// case-insensitive name match.
+async fn resolve_artist_single(db: &SqlitePool, name: &str, mbid: Option<&str>) -> Result<String> {
     let name_trimmed = name.trim();
 
-    let existing = sqlx::query("SELECT id, name FROM artists WHERE name = ? COLLATE NOCASE")
+    if let Some(mbid) = mbid {
+        let by_mbid = sqlx::query("SELECT id FROM artists WHERE mbid = ?")
+            .bind(mbid)
+            .fetch_optional(db)
+            .await?;
+        if let Some(row) = by_mbid {
+            return Ok(row.get("id"));
+        }
+
+        let unclaimed = sqlx::query(
+            "SELECT id FROM artists WHERE name = ? COLLATE NOCASE AND mbid IS NULL LIMIT 1",
+        )
+        .bind(name_trimmed)
+        .fetch_optional(db)
+        .await?;
+        if let Some(row) = unclaimed {
+            let id: String = row.get("id");
+            sqlx::query("UPDATE artists SET mbid = ? WHERE id = ?")
+                .bind(mbid)
+                .bind(&id)
+                .execute(db)
+                .await?;
+            return Ok(id);
+        }
+    } else {
+        let existing = sqlx::query(
+            "SELECT id FROM artists WHERE name = ? COLLATE NOCASE ORDER BY mbid IS NOT NULL LIMIT 1",
+        )
         .bind(name_trimmed)
         .fetch_optional(db)
         .await?;
 
-    if let Some(row) = existing {
-        return Ok(row.get("id"));
+        if let Some(row) = existing {
+            return Ok(row.get("id"));
+        }
     }
 
     let new_id = uuid::Uuid::new_v4().to_string();
 
-    let res = sqlx::query("INSERT OR IGNORE INTO artists (id, name) VALUES (?, ?)")
+    sqlx::query("INSERT INTO artists (id, name, mbid) VALUES (?, ?, ?)")
         .bind(&new_id)
         .bind(name_trimmed)
+        .bind(mbid)
         .execute(db)
         .await?;
 
-    if res.rows_affected() > 0 {
-        Ok(new_id)
-    } else {
-        let row = sqlx::query("SELECT id FROM artists WHERE name = ? COLLATE NOCASE")
-            .bind(name_trimmed)
-            .fetch_one(db)
-            .await?;
-        Ok(row.get("id"))
-    }
+    Ok(new_id)
+}
+
+/// Moves artwork rows from one album to another, keeping the target's own picture
+/// when both have one of the same kind.
+async fn move_album_artwork(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    from_album: &str,
+    to_album: &str,
+) -> Result<()> {
+    sqlx::query("UPDATE OR IGNORE album_artwork SET album_id = ? WHERE album_id = ?")
+        .bind(to_album)
+        .bind(from_album)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM album_artwork WHERE album_id = ?")
+        .bind(from_album)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
 }
 
 async fn resolve_album(
@@ -397,6 +1597,7 @@ async fn resolve_album(
     primary_artist_id: &str,
     all_artist_ids: &[String],
     cover_image: &Option<CoverImageData>,
+    artwork: &[(ArtworkKind, CoverImageData)],
     covers_dir: &Path,
 ) -> Result<String> {
     let existing = sqlx::query("SELECT id FROM albums WHERE title = ? AND artist_id = ?")
@@ -451,6 +1652,27 @@ async fn resolve_album(
             .await?;
     }
 
+    for (kind, img_data) in artwork {
+        match save_cover_art(covers_dir, img_data) {
+            Ok(filename) => {
+                sqlx::query(
+                    "INSERT OR IGNORE INTO album_artwork (album_id, kind, filename) VALUES (?, ?, ?)",
+                )
+                .bind(&album_id)
+                .bind(kind.as_str())
+                .bind(filename)
+                .execute(db)
+                .await?;
+            }
+            Err(e) => log::warn!(
+                "Failed to save {} artwork for {}: {}",
+                kind.as_str(),
+                title,
+                e
+            ),
+        }
+    }
+
     Ok(album_id)
 }
 
@@ -477,8 +1699,8 @@ async fn flush_tracks(
 
         let q = sqlx::query(
             r#"INSERT INTO tracks 
-            (id, path, title, artist_id, album_id, duration_sec, track_number, disc_number, year, genre, bitrate, mtime) 
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (id, path, title, artist_id, album_id, duration_sec, track_number, disc_number, year, genre, bitrate, codec, peak, mtime, media_path, start_offset, end_offset) 
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(path) DO UPDATE SET
             title=excluded.title,
             artist_id=excluded.artist_id,
@@ -489,7 +1711,12 @@ async fn flush_tracks(
             year=excluded.year,
             genre=excluded.genre,
             bitrate=excluded.bitrate,
+            codec=excluded.codec,
+            peak=excluded.peak,
             mtime=excluded.mtime,
+            media_path=excluded.media_path,
+            start_offset=excluded.start_offset,
+            end_offset=excluded.end_offset,
             created_at=CURRENT_TIMESTAMP
             "#
         )
@@ -504,7 +1731,16 @@ async fn flush_tracks(
         .bind(meta.year)
         .bind(&meta.genre)
         .bind(meta.bitrate)
-        .bind(mtime);
+        .bind(meta.codec)
+        .bind(meta.peak)
+        .bind(mtime)
+        .bind(
+            meta.cue
+                .as_ref()
+                .map(|c| c.media_path.to_string_lossy().to_string()),
+        )
+        .bind(meta.cue.as_ref().map(|c| c.start))
+        .bind(meta.cue.as_ref().and_then(|c| c.end));
 
         if let Err(e) = q.execute(&mut *tx).await {
             log::error!("Failed to insert track {}: {}", path_str, e);
@@ -548,6 +1784,16 @@ async fn flush_tracks(
     tracks.clear();
 }
 
+/// `path` with a trailing separator, so a range over it matches whole directories
+/// only and `/music/a` doesn't take `/music/ab`.
+fn dir_prefix(path: &str) -> String {
+    let mut prefix = path.to_string();
+    if !prefix.ends_with(['/', '\\']) {
+        prefix.push(std::path::MAIN_SEPARATOR);
+    }
+    prefix
+}
+
 async fn flush_found(db: &SqlitePool, paths: &mut Vec<PathBuf>) {
     if paths.is_empty() {
         return;
@@ -568,6 +1814,99 @@ async fn flush_found(db: &SqlitePool, paths: &mut Vec<PathBuf>) {
     paths.clear();
 }
 
+/// Opens the library database and runs `PRAGMA integrity_check` on it. A failing
+/// database first gets a `REINDEX`, which fixes the common case of corrupt
+/// indexes. If it is still broken it is moved aside (kept for manual recovery)
+/// and replaced with an empty one, so the library is rebuilt by the next scan
+/// instead of the provider failing to load. Errors that don't show corruption,
+/// such as a locked database, are returned and leave the file alone.
+async fn open_checked(db_path: &Path, provider_id: &str, app: &AppHandle) -> Result<SqlitePool> {
+    let db_url = format!("sqlite://{}", db_path.to_string_lossy());
+    let db = SqlitePool::connect(&db_url).await?;
+
+    let errors = match integrity_errors(&db).await? {
+        None => return Ok(db),
+        Some(errors) => errors,
+    };
+    log::error!(
+        "Library database {} failed integrity check: {}",
+        db_path.display(),
+        errors
+    );
+
+    if sqlx::query("REINDEX").execute(&db).await.is_ok()
+        && matches!(integrity_errors(&db).await, Ok(None))
+    {
+        log::info!(
+            "Repaired library database {} by reindexing",
+            db_path.display()
+        );
+        return Ok(db);
+    }
+    db.close().await;
+
+    let backup = PathBuf::from(format!(
+        "{}.corrupt-{}",
+        db_path.to_string_lossy(),
+        Utc::now().timestamp()
+    ));
+    fs::rename(db_path, &backup).context("Failed to move corrupt database aside")?;
+    for suffix in ["-wal", "-shm"] {
+        let side = PathBuf::from(format!("{}{}", db_path.to_string_lossy(), suffix));
+        if side.exists() {
+            let _ = fs::rename(&side, format!("{}{}", backup.to_string_lossy(), suffix));
+        }
+    }
+    log::warn!(
+        "Moved corrupt library database to {}; starting with an empty library",
+        backup.display()
+    );
+    let _ = LibraryDatabaseReset {
+        provider_id: provider_id.to_string(),
+        backup_path: backup.to_string_lossy().to_string(),
+        errors,
+    }
+    .emit(app);
+
+    fs::File::create(db_path).context("Failed to create database file")?;
+    Ok(SqlitePool::connect(&db_url).await?)
+}
+
+/// `None` when the database is healthy, otherwise the problems SQLite reported.
+async fn integrity_errors(db: &SqlitePool) -> Result<Option<String>> {
+    let rows: Vec<String> = match sqlx::query_scalar("PRAGMA integrity_check(20)")
+        .fetch_all(db)
+        .await
+    {
+        Ok(rows) => rows,
+        // SQLITE_CORRUPT and SQLITE_NOTADB: too damaged to run the check at all.
+        Err(sqlx::Error::Database(e))
+            if e.code()
+                .and_then(|c| c.parse::<i32>().ok())
+                .is_some_and(|c| matches!(c & 0xff, 11 | 26)) =>
+        {
+            return Ok(Some(e.message().to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if rows.len() == 1 && rows[0] == "ok" {
+        return Ok(None);
+    }
+    Ok(Some(rows.join("; ")))
+}
+
+/// Every cover filename the given library database points at.
+async fn referenced_covers(db: &SqlitePool) -> Result<HashSet<String>> {
+    let names: Vec<String> = sqlx::query_scalar(
+        r#"SELECT cover_art FROM albums WHERE cover_art IS NOT NULL
+        UNION SELECT filename FROM album_artwork
+        UNION SELECT cover_art FROM playlists WHERE cover_art IS NOT NULL"#,
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(names.into_iter().collect())
+}
+
 fn save_cover_art(base_dir: &Path, img_data: &CoverImageData) -> Result<String> {
     let ext = match img_data.mime_type.as_str() {
         "image/jpeg" | "image/jpg" => "jpg",
@@ -587,23 +1926,399 @@ fn save_cover_art(base_dir: &Path, img_data: &CoverImageData) -> Result<String>
     Ok(filename)
 }
 
+#[derive(Clone)]
 struct CoverImageData {
     data: Vec<u8>,
     mime_type: String,
 }
 
+#[derive(Clone)]
 struct ParsedMetadata {
     title: String,
     artists: Vec<String>,
+    artist_mbids: Vec<String>,
     album_artist: Option<String>,
+    album_artist_mbid: Option<String>,
     album: String,
+    /// ARTISTSORT, ALBUMARTISTSORT and ALBUMSORT tags.
+    artist_sort: Option<String>,
+    album_artist_sort: Option<String>,
+    album_sort: Option<String>,
     duration: u32,
     track_number: Option<u32>,
     disc_number: Option<u32>,
     year: Option<u16>,
     genre: Option<String>,
     bitrate: Option<u32>,
+    codec: Option<&'static str>,
+    peak: Option<f32>,
     cover_image: Option<CoverImageData>,
+    /// Embedded pictures other than the one used as `cover_image`, first of each kind.
+    artwork: Vec<(ArtworkKind, CoverImageData)>,
+    /// Set for tracks cut from a single file by a cue sheet.
+    cue: Option<CueSegment>,
+}
+
+#[derive(Clone)]
+struct CueSegment {
+    media_path: PathBuf,
+    start: f64,
+    end: Option<f64>,
+}
+
+fn artwork_kind(pic_type: PictureType) -> ArtworkKind {
+    match pic_type {
+        PictureType::CoverFront => ArtworkKind::Front,
+        PictureType::CoverBack => ArtworkKind::Back,
+        PictureType::Leaflet => ArtworkKind::Booklet,
+        PictureType::Media => ArtworkKind::Media,
+        PictureType::Artist | PictureType::LeadArtist | PictureType::Band => ArtworkKind::Artist,
+        _ => ArtworkKind::Other,
+    }
+}
+
+/// Codecs whose files are stored losslessly, as written to `tracks.codec`.
+const LOSSLESS_CODECS: [&str; 6] = ["FLAC", "ALAC", "WAV", "AIFF", "APE", "WAVPACK"];
+
+fn detect_codec(file_type: FileType, bit_depth: Option<u8>) -> Option<&'static str> {
+    Some(match file_type {
+        FileType::Flac => "FLAC",
+        // MP4 holds either AAC or ALAC; only ALAC reports a bit depth.
+        FileType::Mp4 if bit_depth.is_some() => "ALAC",
+        FileType::Mp4 | FileType::Aac => "AAC",
+        FileType::Mpeg => "MP3",
+        FileType::Wav => "WAV",
+        FileType::Aiff => "AIFF",
+        FileType::Ape => "APE",
+        FileType::WavPack => "WAVPACK",
+        FileType::Opus => "OPUS",
+        FileType::Vorbis => "VORBIS",
+        FileType::Speex => "SPEEX",
+        FileType::Mpc => "MPC",
+        _ => return None,
+    })
+}
+
+fn split_mbids(raw: &str) -> Vec<String> {
+    raw.split(['/', ';'])
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// LIKE pattern for a search query, or `None` if the query is blank. A lone
+/// character matches nearly everything as a substring, so it only matches as a
+/// prefix.
+fn search_pattern(query: &str) -> Option<String> {
+    let query = query.trim();
+    if query.is_empty() {
+        None
+    } else if query.chars().count() == 1 {
+        Some(format!("{}%", query))
+    } else {
+        Some(format!("%{}%", query))
+    }
+}
+
+/// Splits a disc marker such as "(Disc 2)", "[CD 1]", "CD2" or "- Disc Two" off
+/// the end of an album title, returning the bare title and the disc number.
+fn strip_disc_suffix(title: &str, keywords: &[String]) -> Option<(String, u32)> {
+    let trimmed = title.trim_end();
+    let (base, marker) = match trimmed.chars().last()? {
+        close @ (')' | ']') => {
+            let open = if close == ')' { '(' } else { '[' };
+            let start = trimmed.rfind(open)?;
+            (&trimmed[..start], &trimmed[start + 1..trimmed.len() - 1])
+        }
+        _ => {
+            // ASCII lowercasing keeps byte offsets valid for slicing `trimmed`.
+            let lower = trimmed.to_ascii_lowercase();
+            let start = keywords
+                .iter()
+                .filter_map(|k| {
+                    lower
+                        .rmatch_indices(&k.to_ascii_lowercase())
+                        .map(|(i, _)| i)
+                        .find(|&i| i > 0 && !lower[..i].ends_with(char::is_alphanumeric))
+                })
+                .max()?;
+            (&trimmed[..start], &trimmed[start..])
+        }
+    };
+
+    let disc = parse_disc_marker(marker, keywords)?;
+    let base = base.trim_end_matches(|c: char| {
+        c.is_whitespace() || matches!(c, '-' | '\u{2013}' | ',' | ':' | '/')
+    });
+    if base.is_empty() {
+        return None;
+    }
+    Some((base.to_string(), disc))
+}
+
+/// Parses "Disc 2", "CD2", "disk two" or "Disc 1 of 2" into the disc number.
+fn parse_disc_marker(marker: &str, keywords: &[String]) -> Option<u32> {
+    let marker = marker.trim();
+    let rest = keywords.iter().find_map(|k| {
+        let head = marker.get(..k.len())?;
+        head.eq_ignore_ascii_case(k).then(|| &marker[k.len()..])
+    })?;
+    let rest =
+        rest.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '.' | ':' | '#'));
+    let (number, tail) = rest.split_at(
+        rest.find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(rest.len()),
+    );
+    let disc = number.parse().ok().or_else(|| {
+        NUMBER_WORDS
+            .iter()
+            .position(|w| w.eq_ignore_ascii_case(number))
+            .map(|i| i as u32 + 1)
+    })?;
+
+    let tail = tail.trim().to_ascii_lowercase();
+    let tail_ok = tail.is_empty()
+        || tail
+            .strip_prefix("of")
+            .or_else(|| tail.strip_prefix('/'))
+            .is_some_and(|total| total.trim().parse::<u32>().is_ok());
+    (tail_ok && disc > 0).then_some(disc)
+}
+
+fn unix_secs(time: std::time::SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[derive(Default)]
+struct CueSheet {
+    performer: Option<String>,
+    title: Option<String>,
+    year: Option<u16>,
+    genre: Option<String>,
+    files: Vec<CueFile>,
+}
+
+struct CueFile {
+    path: PathBuf,
+    tracks: Vec<CueTrack>,
+}
+
+struct CueTrack {
+    number: u32,
+    title: Option<String>,
+    performer: Option<String>,
+    /// Offset of `INDEX 01` into the file, in seconds.
+    start: f64,
+}
+
+/// Audio files claimed by a cue sheet in the same directory, cached per
+/// directory for one scan. Those files are added as the sheet's tracks instead
+/// of as a single long track.
+#[derive(Default)]
+struct CueIndex {
+    dirs: Mutex<HashMap<PathBuf, Arc<HashSet<PathBuf>>>>,
+}
+
+impl CueIndex {
+    fn covers(&self, audio: &Path, charset: Option<&TagCharset>) -> bool {
+        let Some(dir) = audio.parent() else {
+            return false;
+        };
+        let claimed = match self.dirs.lock() {
+            Ok(mut dirs) => dirs
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| Arc::new(cue_claimed_files(dir, charset)))
+                .clone(),
+            Err(_) => return false,
+        };
+        claimed.contains(audio)
+    }
+}
+
+fn cue_claimed_files(dir: &Path, charset: Option<&TagCharset>) -> HashSet<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return HashSet::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("cue"))
+        })
+        .filter_map(|p| read_cue_sheet(&p, charset).ok())
+        .flat_map(|sheet| sheet.files.into_iter().map(|f| f.path))
+        .collect()
+}
+
+/// Key stored in `tracks.path` for a cue track. The cue sheet's path keeps it
+/// under the library root and unique per sheet.
+fn cue_track_path(cue_path: &Path, number: u32) -> PathBuf {
+    PathBuf::from(format!("{}#{:02}", cue_path.to_string_lossy(), number))
+}
+
+fn read_cue_sheet(path: &Path, charset: Option<&TagCharset>) -> Result<CueSheet> {
+    let bytes = fs::read(path)?;
+    let text = decode_cue_text(&bytes, charset);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let sheet = parse_cue_sheet(&text, dir);
+    if sheet.files.is_empty() {
+        return Err(anyhow!("Cue sheet has no tracks"));
+    }
+    Ok(sheet)
+}
+
+/// Cue files from older rippers are often in a legacy code page rather than
+/// UTF-8. Those are decoded with the configured tag encoding, or a detected one.
+fn decode_cue_text(bytes: &[u8], charset: Option<&TagCharset>) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    let encoding = match charset {
+        Some(TagCharset::Fixed(encoding)) => *encoding,
+        _ => {
+            let mut detector = chardetng::EncodingDetector::new();
+            detector.feed(bytes, true);
+            detector.guess(None, true)
+        }
+    };
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+fn parse_cue_sheet(text: &str, dir: &Path) -> CueSheet {
+    let mut sheet = CueSheet::default();
+    for line in text.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let track = sheet.files.last_mut().and_then(|f| f.tracks.last_mut());
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                // `FILE "name.flac" WAVE`; the trailing word is the file type.
+                let name = match rest.strip_prefix('"') {
+                    Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+                    None => rest.rsplit_once(' ').map_or(rest, |(name, _)| name),
+                };
+                sheet.files.push(CueFile {
+                    path: dir.join(name),
+                    tracks: Vec::new(),
+                });
+            }
+            "TRACK" => {
+                if let Some(file) = sheet.files.last_mut() {
+                    let number = rest
+                        .split_whitespace()
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or(file.tracks.len() as u32 + 1);
+                    file.tracks.push(CueTrack {
+                        number,
+                        title: None,
+                        performer: None,
+                        start: 0.0,
+                    });
+                }
+            }
+            "TITLE" => match track {
+                Some(track) => track.title = Some(cue_value(rest)),
+                None => sheet.title = Some(cue_value(rest)),
+            },
+            "PERFORMER" => match track {
+                Some(track) => track.performer = Some(cue_value(rest)),
+                None => sheet.performer = Some(cue_value(rest)),
+            },
+            "INDEX" => {
+                let mut parts = rest.split_whitespace();
+                if let (Some(track), Some("01"), Some(time)) = (track, parts.next(), parts.next()) {
+                    if let Some(start) = parse_cue_time(time) {
+                        track.start = start;
+                    }
+                }
+            }
+            "REM" => {
+                let (key, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                match key.to_ascii_uppercase().as_str() {
+                    "DATE" => sheet.year = value.trim().get(..4).and_then(|y| y.parse().ok()),
+                    "GENRE" => sheet.genre = Some(cue_value(value.trim())),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    sheet.files.retain(|f| !f.tracks.is_empty());
+    sheet
+}
+
+fn cue_value(rest: &str) -> String {
+    match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or_default().to_string(),
+        None => rest.to_string(),
+    }
+}
+
+/// Parses a cue timestamp, `mm:ss:ff` with 75 frames per second.
+fn parse_cue_time(time: &str) -> Option<f64> {
+    let mut parts = time.split(':').map(|p| p.parse::<u32>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(minutes as f64 * 60.0 + seconds as f64 + frames as f64 / 75.0)
+}
+
+/// Builds each track in `file` from the audio file's own tags, with whatever
+/// the cue sheet says taking precedence. Only the first track carries the
+/// cover art, since the album is resolved once for all of them.
+fn cue_tracks(
+    cue_path: &Path,
+    sheet: &CueSheet,
+    file: &CueFile,
+    mut audio: ParsedMetadata,
+) -> Vec<(PathBuf, ParsedMetadata)> {
+    let mut cover_image = audio.cover_image.take();
+    let mut artwork = std::mem::take(&mut audio.artwork);
+    let total = audio.duration as f64;
+    if let Some(performer) = &sheet.performer {
+        audio.album_artist = Some(performer.clone());
+        audio.album_artist_mbid = None;
+        audio.album_artist_sort = None;
+    }
+    if let Some(title) = &sheet.title {
+        audio.album = title.clone();
+        audio.album_sort = None;
+    }
+    audio.year = sheet.year.or(audio.year);
+    audio.genre = sheet.genre.clone().or(audio.genre);
+
+    let mut tracks = Vec::with_capacity(file.tracks.len());
+    for (i, track) in file.tracks.iter().enumerate() {
+        let end = file.tracks.get(i + 1).map(|next| next.start);
+        let mut meta = audio.clone();
+        meta.title = track
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("Track {}", track.number));
+        if let Some(performer) = track.performer.as_ref().or(sheet.performer.as_ref()) {
+            let artists = split_artists(performer);
+            if !artists.is_empty() {
+                meta.artists = artists;
+                meta.artist_mbids.clear();
+                meta.artist_sort = None;
+            }
+        }
+        meta.track_number = Some(track.number);
+        meta.duration = (end.unwrap_or(total) - track.start).max(0.0) as u32;
+        meta.cover_image = cover_image.take();
+        meta.artwork = std::mem::take(&mut artwork);
+        meta.cue = Some(CueSegment {
+            media_path: file.path.clone(),
+            start: track.start,
+            end,
+        });
+        tracks.push((cue_track_path(cue_path, track.number), meta));
+    }
+    tracks
 }
 
 fn split_artists(raw: &str) -> Vec<String> {
@@ -620,11 +2335,173 @@ fn split_artists(raw: &str) -> Vec<String> {
         .collect()
 }
 
-fn parse_metadata(path: &Path) -> Result<ParsedMetadata> {
+#[derive(Default)]
+struct FilenameFields {
+    track: Option<u32>,
+    artist: Option<String>,
+    album: Option<String>,
+    title: Option<String>,
+}
+
+/// Matches a file stem against a user pattern such as `{track} {artist} - {title}`.
+/// Supported placeholders are `{track}`, `{artist}`, `{album}` and `{title}`; any
+/// other text must appear literally. Returns `None` when the stem doesn't fit.
+fn parse_filename_pattern(stem: &str, pattern: &str) -> Option<FilenameFields> {
+    let mut fields = FilenameFields::default();
+    let mut rest = stem;
+    let mut pat = pattern;
+
+    while !pat.is_empty() {
+        if let Some(after) = pat.strip_prefix('{') {
+            let end = after.find('}')?;
+            let name = &after[..end];
+            pat = &after[end + 1..];
+
+            let literal = &pat[..pat.find('{').unwrap_or(pat.len())];
+            let value_len = if name == "track" && literal.is_empty() {
+                rest.find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len())
+            } else if literal.is_empty() {
+                if !pat.is_empty() {
+                    return None;
+                }
+                rest.len()
+            } else {
+                rest.find(literal)?
+            };
+
+            let value = rest[..value_len].trim();
+            rest = &rest[value_len..];
+            if value.is_empty() {
+                return None;
+            }
+            match name {
+                "track" => fields.track = Some(value.parse().ok()?),
+                "artist" => fields.artist = Some(value.to_string()),
+                "album" => fields.album = Some(value.to_string()),
+                "title" => fields.title = Some(value.to_string()),
+                _ => return None,
+            }
+        } else {
+            let literal_len = pat.find('{').unwrap_or(pat.len());
+            rest = rest.strip_prefix(&pat[..literal_len])?;
+            pat = &pat[literal_len..];
+        }
+    }
+
+    rest.is_empty().then_some(fields)
+}
+
+/// Splits a file stem into its ` - ` separated parts and takes a track number off
+/// the front, e.g. `01 - Artist - Title` or `Artist - 3. Title`. Only the title part
+/// loses a leading number, so names like `3 Doors Down - Kryptonite` stay intact.
+fn split_filename_stem(stem: &str) -> (Option<u32>, Vec<&str>) {
+    let mut parts: Vec<&str> = stem.split(" - ").map(str::trim).collect();
+    if parts.len() > 1 && parts[0].len() <= 3 && parts[0].chars().all(|c| c.is_ascii_digit()) {
+        let number = parts.remove(0).parse().ok();
+        return (number, parts);
+    }
+
+    let last = parts.len() - 1;
+    let (number, title) = strip_track_number(parts[last]);
+    parts[last] = title;
+    (number, parts)
+}
+
+/// Splits a leading track number off a title, e.g. `3. Title` or `03_Title`. The
+/// number must be followed by `.`, `-` or `_`; whitespace alone is part of the title,
+/// as in `99 Luftballons`.
+fn strip_track_number(title: &str) -> (Option<u32>, &str) {
+    let digits = title
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(title.len());
+    if digits == 0 || digits > 3 {
+        return (None, title);
+    }
+
+    let after = title[digits..].trim_start_matches(' ');
+    if !after.starts_with(['.', '-', '_']) {
+        return (None, title);
+    }
+    let rest = after.trim_start_matches([' ', '.', '-', '_']);
+    if rest.is_empty() {
+        return (None, title);
+    }
+    (title[..digits].parse().ok(), rest)
+}
+
+enum TagCharset {
+    Detect,
+    Fixed(&'static encoding_rs::Encoding),
+}
+
+impl TagCharset {
+    /// Stored with the library so a changed setting can force files to be re-read.
+    fn setting(charset: Option<&TagCharset>) -> &'static str {
+        match charset {
+            None => "",
+            Some(TagCharset::Detect) => "detect",
+            Some(TagCharset::Fixed(encoding)) => encoding.name(),
+        }
+    }
+}
+
+/// Ids of the ID3v2 text frames in an MP3 that are flagged as Latin-1. Text in
+/// other ID3v2 encodings is already Unicode and must not be repaired.
+fn latin1_id3v2_frames(path: &Path) -> HashSet<String> {
+    let mut frames = HashSet::new();
+    let Ok(mut file) = fs::File::open(path) else {
+        return frames;
+    };
+    let Ok(mpeg) = lofty::mpeg::MpegFile::read_from(&mut file, lofty::config::ParseOptions::new())
+    else {
+        return frames;
+    };
+    if let Some(tag) = mpeg.id3v2() {
+        for frame in tag {
+            if let lofty::id3::v2::Frame::Text(text) = frame {
+                if text.encoding == lofty::TextEncoding::Latin1 {
+                    frames.insert(frame.id_str().to_string());
+                }
+            }
+        }
+    }
+    frames
+}
+
+/// ID3v1 tags and ID3v2 text frames flagged as Latin-1 are often really in a local
+/// codepage such as Windows-1251. Lofty decodes them byte-for-byte, so every char
+/// stays below U+0100; turn them back into bytes and decode with the right charset.
+fn repair_tag_text(text: &str, charset: &TagCharset) -> Option<String> {
+    if text.is_ascii() || text.chars().any(|c| c as u32 > 0xFF) {
+        return None;
+    }
+    let bytes: Vec<u8> = text.chars().map(|c| c as u8).collect();
+    let encoding = match charset {
+        TagCharset::Fixed(encoding) => *encoding,
+        TagCharset::Detect => {
+            let mut detector = chardetng::EncodingDetector::new();
+            detector.feed(&bytes, true);
+            detector.guess(None, true)
+        }
+    };
+    if encoding == encoding_rs::WINDOWS_1252 {
+        return None;
+    }
+    let (decoded, _, had_errors) = encoding.decode(&bytes);
+    (!had_errors).then(|| decoded.into_owned())
+}
+
+fn parse_metadata(
+    path: &Path,
+    filename_pattern: Option<&str>,
+    tag_charset: Option<&TagCharset>,
+) -> Result<ParsedMetadata> {
     let tagged_file = read_from_path(path).map_err(|e| anyhow!("Lofty read error: {}", e))?;
     let properties = tagged_file.properties();
-    let duration = properties.duration().as_secs() as u32;
+    let duration = u32::try_from(properties.duration().as_secs()).unwrap_or(u32::MAX);
     let bitrate = properties.audio_bitrate();
+    let codec = detect_codec(tagged_file.file_type(), properties.bit_depth());
 
     let filename_str = path
         .file_stem()
@@ -632,29 +2509,52 @@ fn parse_metadata(path: &Path) -> Result<ParsedMetadata> {
         .to_string_lossy()
         .to_string();
     let mut title = filename_str.clone();
-    let mut artists = vec!["Unknown Artist".to_string()];
-    let mut album = "Unknown Album".to_string();
+    let mut artists = vec![UNKNOWN_ARTIST.to_string()];
+    let mut album = UNKNOWN_ALBUM.to_string();
 
     let mut track_number = None;
     let mut disc_number = None;
     let mut year = None;
     let mut genre = None;
     let mut cover_image = None;
+    let mut artwork: Vec<(ArtworkKind, CoverImageData)> = Vec::new();
     let mut album_artist = None;
+    let mut artist_mbids = Vec::new();
+    let mut album_artist_mbid = None;
+    let mut artist_sort = None;
+    let mut album_artist_sort = None;
+    let mut album_sort = None;
+    let mut peak = None;
     if let Some(tag) = tagged_file.primary_tag().or(tagged_file.first_tag()) {
+        // Only text that went through Latin-1 can hide a legacy codepage.
+        let latin1_frames = match (tag_charset, tag.tag_type()) {
+            (Some(_), TagType::Id3v2) => latin1_id3v2_frames(path),
+            _ => HashSet::new(),
+        };
+        let fix_text = |text: &str, frame_id: &str| -> String {
+            let repairable = match tag.tag_type() {
+                TagType::Id3v1 => true,
+                TagType::Id3v2 => latin1_frames.contains(frame_id),
+                _ => false,
+            };
+            tag_charset
+                .filter(|_| repairable)
+                .and_then(|charset| repair_tag_text(text, charset))
+                .unwrap_or_else(|| text.to_string())
+        };
         if let Some(t) = tag.title() {
             if !t.trim().is_empty() {
-                title = t.trim().to_string();
+                title = fix_text(t.trim(), "TIT2");
             }
         }
         if let Some(a) = tag.artist() {
             if !a.trim().is_empty() {
-                artists = split_artists(&a);
+                artists = split_artists(&fix_text(a.trim(), "TPE1"));
             }
         }
         if let Some(a) = tag.album() {
             if !a.trim().is_empty() {
-                album = a.trim().to_string();
+                album = fix_text(a.trim(), "TALB");
             }
         }
         track_number = tag.track();
@@ -664,46 +2564,97 @@ fn parse_metadata(path: &Path) -> Result<ParsedMetadata> {
         }
         if let Some(g) = tag.genre() {
             if !g.trim().is_empty() {
-                genre = Some(g.trim().to_string());
+                genre = Some(fix_text(g.trim(), "TCON"));
             }
         }
         if let Some(aa) = tag.get_string(&ItemKey::AlbumArtist) {
             if !aa.trim().is_empty() {
-                album_artist = Some(aa.trim().to_string());
+                album_artist = Some(fix_text(aa.trim(), "TPE2"));
             }
         }
+        let sort_tag = |key: &ItemKey, frame_id: &str| {
+            tag.get_string(key)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| fix_text(s, frame_id))
+        };
+        artist_sort = sort_tag(&ItemKey::TrackArtistSortOrder, "TSOP");
+        album_artist_sort = sort_tag(&ItemKey::AlbumArtistSortOrder, "TSO2");
+        album_sort = sort_tag(&ItemKey::AlbumTitleSortOrder, "TSOA");
+        if let Some(ids) = tag.get_string(&ItemKey::MusicBrainzArtistId) {
+            artist_mbids = split_mbids(ids);
+        }
+        if let Some(ids) = tag.get_string(&ItemKey::MusicBrainzReleaseArtistId) {
+            album_artist_mbid = split_mbids(ids).into_iter().next();
+        }
+        // The scan doesn't decode audio, so only ReplayGain-tagged files get a peak.
+        if let Some(p) = tag.get_string(&ItemKey::ReplayGainTrackPeak) {
+            peak = p
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|p| p.is_finite() && *p >= 0.0);
+        }
 
         let pictures = tag.pictures();
         if !pictures.is_empty() {
-            let pic = pictures
+            let to_image_data = |p: &lofty::picture::Picture| CoverImageData {
+                data: p.data().to_vec(),
+                mime_type: p
+                    .mime_type()
+                    .map(|m| m.to_string())
+                    .unwrap_or("image/jpeg".to_string()),
+            };
+            let cover_idx = pictures
                 .iter()
-                .find(|p| p.pic_type() == lofty::picture::PictureType::CoverFront)
-                .or_else(|| pictures.first());
-            if let Some(p) = pic {
-                cover_image = Some(CoverImageData {
-                    data: p.data().to_vec(),
-                    mime_type: p
-                        .mime_type()
-                        .map(|m| m.to_string())
-                        .unwrap_or("image/jpeg".to_string()),
-                });
+                .position(|p| p.pic_type() == PictureType::CoverFront)
+                .unwrap_or(0);
+            cover_image = Some(to_image_data(&pictures[cover_idx]));
+
+            for (idx, p) in pictures.iter().enumerate() {
+                let kind = artwork_kind(p.pic_type());
+                if idx == cover_idx || artwork.iter().any(|(k, _)| *k == kind) {
+                    continue;
+                }
+                artwork.push((kind, to_image_data(p)));
             }
         }
     }
 
-    if artists.len() == 1 && artists[0] == "Unknown Artist" {
-        let parts: Vec<&str> = filename_str.split(" - ").collect();
-        match parts.len() {
-            3 => {
-                artists = split_artists(parts[0]);
-                album = parts[1].trim().to_string();
-                title = parts[2].trim().to_string();
+    if artists.len() == 1 && artists[0] == UNKNOWN_ARTIST {
+        let pattern_fields =
+            filename_pattern.and_then(|p| parse_filename_pattern(&filename_str, p));
+        if let Some(fields) = pattern_fields {
+            if let Some(a) = fields.artist {
+                artists = split_artists(&a);
             }
-            2 => {
-                artists = split_artists(parts[0]);
-                title = parts[1].trim().to_string();
+            if let Some(a) = fields.album {
+                album = a;
+            }
+            if let Some(t) = fields.title {
+                title = t;
+            }
+            track_number = track_number.or(fields.track);
+        } else {
+            let (number, parts) = split_filename_stem(&filename_str);
+            track_number = track_number.or(number);
+
+            match parts.len() {
+                3 => {
+                    artists = split_artists(parts[0]);
+                    album = parts[1].to_string();
+                    title = parts[2].to_string();
+                }
+                2 => {
+                    artists = split_artists(parts[0]);
+                    title = parts[1].to_string();
+                }
+                _ => {
+                    if title == filename_str {
+                        title = parts.join(" - ");
+                    }
+                }
             }
-            _ => {}
         }
     }
 
@@ -711,14 +2662,23 @@ fn parse_metadata(path: &Path) -> Result<ParsedMetadata> {
         title,
         artists,
         album,
+        artist_sort,
+        album_artist_sort,
+        album_sort,
         album_artist,
+        album_artist_mbid,
+        artist_mbids,
         duration,
         track_number,
         disc_number,
         year,
         genre,
         bitrate,
+        codec,
+        peak,
         cover_image,
+        artwork,
+        cue: None,
     })
 }
 
@@ -728,7 +2688,29 @@ impl LibraryProvider for LocalProvider {
         &self.id
     }
     fn name(&self) -> &str {
-        "Local Library"
+        self.config
+            .sources
+            .iter()
+            .find_map(|s| match s {
+                SourceConfig::Local { id, name, .. } if id == &self.id => Some(name.as_str()),
+                _ => None,
+            })
+            .unwrap_or("Local Library")
+    }
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            can_create_playlist: true,
+            can_rename_playlist: true,
+            can_add_to_playlist: true,
+            can_remove_from_playlist: true,
+            writable_favorites: true,
+            can_rate: true,
+            has_radio: false,
+            can_scan: true,
+            can_cancel_scan: true,
+            can_edit_library: true,
+            has_podcasts: false,
+        }
     }
 
     async fn get_artist_albums(&self, artist_id: &str) -> Result<Vec<Album>, String> {
@@ -745,26 +2727,90 @@ impl LibraryProvider for LocalProvider {
         .fetch_all(&self.db)
         .await
         .map_err(|e| e.to_string())?;
-        Ok(rows.into_iter().map(map_row_to_album).collect())
+        Ok(rows.into_iter().map(|r| self.map_album(r)).collect())
     }
 
-    async fn get_album_tracks(&self, album_id: &str) -> Result<Vec<Track>, String> {
+    async fn get_artist_features(&self, artist_id: &str) -> Result<Vec<ArtistFeature>, String> {
         let rows = sqlx::query(
+            r#"SELECT DISTINCT t.*, a.name as artist_name, al.title as album_title
+            FROM track_artists ta
+            JOIN tracks t ON ta.track_id = t.id
+            JOIN albums al ON t.album_id = al.id
+            LEFT JOIN artists a ON t.artist_id = a.id
+            WHERE ta.artist_id = ? AND al.artist_id != ?
+            ORDER BY al.year DESC, COALESCE(al.sort_name, al.title) COLLATE NOCASE ASC,
+                t.disc_number ASC, t.track_number ASC"#,
+        )
+        .bind(artist_id)
+        .bind(artist_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let mut features: Vec<ArtistFeature> = Vec::new();
+        for row in rows {
+            let track = self.map_track(row);
+            if let Some(feature) = features.iter_mut().find(|f| f.album.id == track.album_id) {
+                feature.tracks.push(track);
+                continue;
+            }
+            let album = self.get_album(&track.album_id).await?;
+            features.push(ArtistFeature {
+                album,
+                tracks: vec![track],
+            });
+        }
+        Ok(features)
+    }
+
+    async fn get_artist_tracks(
+        &self,
+        artist_id: &str,
+        sort: TrackSort,
+        limit: Option<u32>,
+    ) -> Result<Vec<Track>, String> {
+        let order_by = match sort {
+            TrackSort::Album => {
+                "COALESCE(al.sort_name, al.title) COLLATE NOCASE ASC, t.disc_number ASC, t.track_number ASC"
+            }
+            TrackSort::Title => "t.title COLLATE NOCASE ASC",
+            TrackSort::MostPlayed => "t.play_count DESC, t.title COLLATE NOCASE ASC",
+            TrackSort::Newest => "t.year DESC, t.created_at DESC",
+        };
+        let rows = sqlx::query(&format!(
+            r#"SELECT DISTINCT t.*, a.name as artist_name, al.title as album_title
+            FROM track_artists ta
+            JOIN tracks t ON ta.track_id = t.id
+            LEFT JOIN albums al ON t.album_id = al.id
+            LEFT JOIN artists a ON t.artist_id = a.id
+            WHERE ta.artist_id = ?
+            ORDER BY {}
+            LIMIT ?"#,
+            order_by
+        ))
+        .bind(artist_id)
+        .bind(limit.map(|l| l as i64).unwrap_or(-1))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
+    }
+
+    async fn get_album_tracks(&self, album_id: &str) -> Result<Vec<Track>, String> {
+        let rows = sqlx::query(&format!(
             r#"SELECT t.*, a.name as artist_name, al.title as album_title
             FROM tracks t
             LEFT JOIN artists a ON t.artist_id = a.id
             LEFT JOIN albums al ON t.album_id = al.id
             WHERE t.album_id = ? 
-            ORDER BY t.disc_number ASC, t.track_number ASC"#,
-        )
+            ORDER BY {}"#,
+            ALBUM_TRACK_ORDER
+        ))
         .bind(album_id)
         .fetch_all(&self.db)
         .await
         .map_err(|e| e.to_string())?;
-        Ok(rows
-            .into_iter()
-            .map(|r| map_row_to_track(r, Some(self.id.clone())))
-            .collect())
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
     }
 
     async fn get_recent_albums(&self, limit: u32) -> Result<Vec<Album>, String> {
@@ -779,7 +2825,7 @@ impl LibraryProvider for LocalProvider {
         .fetch_all(&self.db)
         .await
         .map_err(|e| e.to_string())?;
-        Ok(rows.into_iter().map(map_row_to_album).collect())
+        Ok(rows.into_iter().map(|r| self.map_album(r)).collect())
     }
 
     async fn get_random_albums(&self, limit: u32) -> Result<Vec<Album>, String> {
@@ -794,7 +2840,7 @@ impl LibraryProvider for LocalProvider {
         .fetch_all(&self.db)
         .await
         .map_err(|e| e.to_string())?;
-        Ok(rows.into_iter().map(map_row_to_album).collect())
+        Ok(rows.into_iter().map(|r| self.map_album(r)).collect())
     }
 
     async fn get_most_played_tracks(&self, limit: u32) -> Result<Vec<Track>, String> {
@@ -810,10 +2856,7 @@ impl LibraryProvider for LocalProvider {
         .fetch_all(&self.db)
         .await
         .map_err(|e| e.to_string())?;
-        Ok(rows
-            .into_iter()
-            .map(|r| map_row_to_track(r, Some(self.id.clone())))
-            .collect())
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
     }
 
     async fn get_library_stats(&self) -> Result<crate::models::entities::LibraryStats, String> {
@@ -848,7 +2891,7 @@ impl LibraryProvider for LocalProvider {
             album_count: album_count as u32,
             track_count: track_count as u32,
             artist_count: artist_count as u32,
-            total_duration: total_duration.try_into().unwrap_or(u32::MAX),
+            total_duration: total_duration.try_into().unwrap_or(0),
             average_bitrate: average_bitrate.try_into().unwrap_or(u32::MAX),
         })
     }
@@ -887,10 +2930,7 @@ impl LibraryProvider for LocalProvider {
         .fetch_all(&self.db)
         .await
         .map_err(|e| e.to_string())?;
-        Ok(rows
-            .into_iter()
-            .map(|r| map_row_to_track(r, Some(self.id.clone())))
-            .collect())
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
     }
 
     async fn get_favorites(&self) -> Result<Vec<Track>, String> {
@@ -903,61 +2943,79 @@ impl LibraryProvider for LocalProvider {
             ORDER BY t.created_at DESC"#,
         )
         .fetch_all(&self.db)
-        .await
-        .map_err(|e| e.to_string())?;
-        Ok(rows
-            .into_iter()
-            .map(|r| map_row_to_track(r, Some(self.id.clone())))
-            .collect())
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
     }
 
-    async fn search(&self, query: &str) -> Result<UnifiedSearchResult, String> {
-        let pattern = format!("%{}%", query);
+    async fn search(
+        &self,
+        query: &str,
+        types: &[SearchType],
+    ) -> Result<UnifiedSearchResult, String> {
+        let Some(pattern) = search_pattern(query) else {
+            return Ok(UnifiedSearchResult::default());
+        };
 
-        let tracks_future = sqlx::query(
-            r#"SELECT DISTINCT t.*, a.name as artist_name, al.title as album_title 
-               FROM tracks t 
-               LEFT JOIN artists a ON t.artist_id = a.id
-               LEFT JOIN albums al ON t.album_id = al.id
-               WHERE t.title LIKE ? OR a.name LIKE ? LIMIT 20"#,
-        )
-        .bind(&pattern)
-        .bind(&pattern)
-        .fetch_all(&self.db);
-
-        let albums_future = sqlx::query(
-            r#"SELECT 
-                al.id, al.title, al.artist_id, al.year, al.cover_art, 
-                ar.name as artist_name,
-                (SELECT COUNT(*) FROM tracks WHERE album_id = al.id) as track_count
-            FROM albums al
-            LEFT JOIN artists ar ON al.artist_id = ar.id
-            WHERE al.title LIKE ? OR ar.name LIKE ?
-            LIMIT 20"#,
-        )
-        .bind(&pattern)
-        .bind(&pattern)
-        .fetch_all(&self.db);
+        let tracks_future = async {
+            if !types.contains(&SearchType::Tracks) {
+                return Ok(Vec::new());
+            }
+            sqlx::query(
+                r#"SELECT DISTINCT t.*, a.name as artist_name, al.title as album_title 
+                   FROM tracks t 
+                   LEFT JOIN artists a ON t.artist_id = a.id
+                   LEFT JOIN albums al ON t.album_id = al.id
+                   WHERE t.title LIKE ? OR a.name LIKE ? LIMIT 20"#,
+            )
+            .bind(&pattern)
+            .bind(&pattern)
+            .fetch_all(&self.db)
+            .await
+        };
 
-        let artists_future = sqlx::query(r#"SELECT * FROM artists WHERE name LIKE ? LIMIT 20"#)
+        let albums_future = async {
+            if !types.contains(&SearchType::Albums) {
+                return Ok(Vec::new());
+            }
+            sqlx::query(
+                r#"SELECT 
+                    al.id, al.title, al.artist_id, al.year, al.cover_art, 
+                    ar.name as artist_name,
+                    (SELECT COUNT(*) FROM tracks WHERE album_id = al.id) as track_count
+                FROM albums al
+                LEFT JOIN artists ar ON al.artist_id = ar.id
+                WHERE al.title LIKE ? OR ar.name LIKE ?
+                LIMIT 20"#,
+            )
+            .bind(&pattern)
             .bind(&pattern)
-            .fetch_all(&self.db);
+            .fetch_all(&self.db)
+            .await
+        };
+
+        let artists_future = async {
+            if !types.contains(&SearchType::Artists) {
+                return Ok(Vec::new());
+            }
+            sqlx::query(r#"SELECT * FROM artists WHERE name LIKE ? LIMIT 20"#)
+                .bind(&pattern)
+                .fetch_all(&self.db)
+                .await
+        };
 
         let (track_rows, album_rows, artist_rows) =
             tokio::try_join!(tracks_future, albums_future, artists_future)
                 .map_err(|e| e.to_string())?;
 
         Ok(UnifiedSearchResult {
-            tracks: track_rows
-                .into_iter()
-                .map(|r| map_row_to_track(r, Some(self.id.clone())))
-                .collect(),
-            albums: album_rows.into_iter().map(map_row_to_album).collect(),
+            tracks: track_rows.into_iter().map(|r| self.map_track(r)).collect(),
+            albums: album_rows.into_iter().map(|r| self.map_album(r)).collect(),
             artists: artist_rows
                 .into_iter()
                 .map(|row| Artist {
                     id: row.get("id"),
-                    name: row.get("name"),
+                    name: self.artist_label(row.get("name")),
                     bio: row.try_get("bio").unwrap_or_default(),
                     image_url: row.try_get("image_url").unwrap_or_default(),
                 })
@@ -974,18 +3032,39 @@ impl LibraryProvider for LocalProvider {
             .ok_or("Artist not found".to_string())?;
         Ok(Artist {
             id: row.get("id"),
-            name: row.get("name"),
+            name: self.artist_label(row.get("name")),
             bio: row.try_get("bio").unwrap_or_default(),
             image_url: row.try_get("image_url").unwrap_or_default(),
         })
     }
     async fn get_track(&self, track_id: &str) -> Result<Track, String> {
         let row = sqlx::query(r#"SELECT t.*, a.name as artist_name, al.title as album_title FROM tracks t LEFT JOIN artists a ON t.artist_id = a.id LEFT JOIN albums al ON t.album_id = al.id WHERE t.id = ?"#).bind(track_id).fetch_optional(&self.db).await.map_err(|e| e.to_string())?.ok_or("Track not found".to_string())?;
-        Ok(map_row_to_track(row, Some(self.id.clone())))
+        Ok(self.map_track(row))
+    }
+    async fn get_tracks(&self, track_ids: &[String]) -> Result<Vec<Track>, String> {
+        let mut tracks = Vec::with_capacity(track_ids.len());
+        for chunk in track_ids.chunks(500) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                r#"SELECT t.*, a.name as artist_name, al.title as album_title
+                FROM tracks t
+                LEFT JOIN artists a ON t.artist_id = a.id
+                LEFT JOIN albums al ON t.album_id = al.id
+                WHERE t.id IN ({})"#,
+                placeholders
+            );
+            let mut query = sqlx::query(&sql);
+            for id in chunk {
+                query = query.bind(id);
+            }
+            let rows = query.fetch_all(&self.db).await.map_err(|e| e.to_string())?;
+            tracks.extend(rows.into_iter().map(|r| self.map_track(r)));
+        }
+        Ok(tracks)
     }
     async fn get_album(&self, album_id: &str) -> Result<Album, String> {
         let row = sqlx::query(r#"SELECT id, title, artist_id, year, cover_art, (SELECT name FROM artists WHERE id = albums.artist_id) as artist_name, (SELECT COUNT(*) FROM tracks WHERE album_id = albums.id) as track_count FROM albums WHERE id = ?"#).bind(album_id).fetch_optional(&self.db).await.map_err(|e| e.to_string())?.ok_or("Album not found".to_string())?;
-        Ok(map_row_to_album(row))
+        Ok(self.map_album(row))
     }
     async fn set_track_liked(&self, track_id: &str, liked: bool) -> Result<(), String> {
         sqlx::query("UPDATE tracks SET liked = ? WHERE id = ?")
@@ -996,8 +3075,277 @@ impl LibraryProvider for LocalProvider {
             .map_err(|e| e.to_string())?;
         Ok(())
     }
+    async fn set_track_explicit(&self, track_id: &str, explicit: bool) -> Result<(), String> {
+        sqlx::query("UPDATE tracks SET explicit = ? WHERE id = ?")
+            .bind(explicit)
+            .bind(track_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    async fn set_track_gain(&self, track_id: &str, gain_db: Option<f32>) -> Result<(), String> {
+        let res = sqlx::query("UPDATE tracks SET gain_db = ? WHERE id = ?")
+            .bind(gain_db)
+            .bind(track_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        if res.rows_affected() == 0 {
+            return Err("Track not found".to_string());
+        }
+        Ok(())
+    }
+    async fn record_skip(&self, track_id: &str) -> Result<(), String> {
+        sqlx::query("UPDATE tracks SET skip_count = skip_count + 1 WHERE id = ?")
+            .bind(track_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn set_track_rating(&self, track_id: &str, rating: u8) -> Result<(), String> {
+        let rating = (rating > 0).then_some(rating);
+        sqlx::query("UPDATE tracks SET rating = ? WHERE id = ?")
+            .bind(rating)
+            .bind(track_id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    async fn get_tracks_by_rating(&self, min_rating: u8) -> Result<Vec<Track>, String> {
+        let rows = sqlx::query(
+            r#"SELECT t.*, a.name as artist_name, al.title as album_title
+            FROM tracks t
+            LEFT JOIN artists a ON t.artist_id = a.id
+            LEFT JOIN albums al ON t.album_id = al.id
+            WHERE t.rating >= ?
+            ORDER BY t.rating DESC, t.title COLLATE NOCASE ASC"#,
+        )
+        .bind(min_rating.max(1))
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
+    }
+    async fn merge_artists(
+        &self,
+        keep_id: &str,
+        merge_id: &str,
+    ) -> Result<ArtistMergeResult, String> {
+        self.merge_artists_tx(keep_id, merge_id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+    async fn get_album_artwork(&self, album_id: &str) -> Result<Vec<AlbumArtwork>, String> {
+        let album = self.get_album(album_id).await?;
+        let mut artwork: Vec<AlbumArtwork> = album
+            .cover_art
+            .map(|url| AlbumArtwork {
+                kind: ArtworkKind::Front,
+                url,
+            })
+            .into_iter()
+            .collect();
+
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT kind, filename FROM album_artwork WHERE album_id = ? ORDER BY rowid",
+        )
+        .bind(album_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| e.to_string())?;
+        artwork.extend(rows.into_iter().map(|(kind, url)| AlbumArtwork {
+            kind: ArtworkKind::parse(&kind),
+            url,
+        }));
+        Ok(artwork)
+    }
+    async fn prefetch_album(&self, _album_id: &str) -> Result<(), String> {
+        Ok(())
+    }
+    async fn cleanup_covers(&self) -> Result<CoverCleanupResult, String> {
+        let (albums_relinked, albums_cleared) = self
+            .relink_missing_covers()
+            .await
+            .map_err(|e| e.to_string())?;
+        let (files_removed, bytes_reclaimed) = self
+            .prune_unreferenced_covers()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(CoverCleanupResult {
+            files_removed,
+            bytes_reclaimed,
+            albums_relinked,
+            albums_cleared,
+        })
+    }
+    async fn get_cover_image(&self, album_id: &str) -> Result<Vec<u8>, String> {
+        let cover: Option<String> = sqlx::query_scalar("SELECT cover_art FROM albums WHERE id = ?")
+            .bind(album_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| e.to_string())?
+            .flatten();
+        let filename = cover.ok_or("Album has no cover".to_string())?;
+        // Covers found by Last.fm enrichment are stored as the image URL rather
+        // than a file under covers/.
+        if filename.starts_with("http://") || filename.starts_with("https://") {
+            let res = reqwest::get(&filename)
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| e.to_string())?;
+            return res
+                .bytes()
+                .await
+                .map(|b| b.to_vec())
+                .map_err(|e| e.to_string());
+        }
+        tokio::fs::read(self.data_dir.join(COVERS_DIR).join(filename))
+            .await
+            .map_err(|e| e.to_string())
+    }
+    async fn get_lyrics(
+        &self,
+        track_id: &str,
+        source: LyricsSource,
+    ) -> Result<Option<Lyrics>, String> {
+        let row: Option<(String, Option<String>)> =
+            sqlx::query_as("SELECT path, media_path FROM tracks WHERE id = ?")
+                .bind(track_id)
+                .fetch_optional(&self.db)
+                .await
+                .map_err(|e| e.to_string())?;
+        let (path, media_path) = row.ok_or("Track not found".to_string())?;
+        // Tags and sidecars of a cue rip describe the whole file, not this track.
+        if media_path.is_some() {
+            return Ok(None);
+        }
+
+        let path = PathBuf::from(path);
+        let text = match source {
+            LyricsSource::Embedded => tokio::task::spawn_blocking(move || {
+                let tagged_file = read_from_path(&path).ok()?;
+                let tag = tagged_file.primary_tag().or(tagged_file.first_tag())?;
+                tag.get_string(&ItemKey::Lyrics).map(str::to_string)
+            })
+            .await
+            .map_err(|e| e.to_string())?,
+            LyricsSource::Sidecar => tokio::fs::read_to_string(path.with_extension("lrc"))
+                .await
+                .ok(),
+            LyricsSource::Provider | LyricsSource::Lrclib => None,
+        };
+        Ok(text.and_then(|text| Lyrics::from_text(source, text)))
+    }
+    async fn get_audio_tags(
+        &self,
+        track_id: &str,
+    ) -> Result<BTreeMap<String, Vec<String>>, String> {
+        let path: String =
+            sqlx::query_scalar("SELECT COALESCE(media_path, path) FROM tracks WHERE id = ?")
+                .bind(track_id)
+                .fetch_optional(&self.db)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or("Track not found".to_string())?;
+
+        tokio::task::spawn_blocking(move || {
+            let tagged_file = read_from_path(&path).map_err(|e| e.to_string())?;
+            let mut tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            for tag in tagged_file.tags() {
+                for item in tag.items() {
+                    let key = item
+                        .key()
+                        .map_key(tag.tag_type(), true)
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("{:?}", item.key()));
+                    let value = match item.value() {
+                        ItemValue::Text(text) | ItemValue::Locator(text) => text.clone(),
+                        ItemValue::Binary(data) => format!("<{} bytes>", data.len()),
+                    };
+                    tags.entry(key).or_default().push(value);
+                }
+            }
+            Ok(tags)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+    async fn remove_tracks_under_path(&self, path_prefix: &str) -> Result<u32, String> {
+        self.remove_tracks_under_path_tx(path_prefix)
+            .await
+            .map_err(|e| e.to_string())
+    }
+    async fn merge_albums(&self, keep_id: &str, merge_id: &str) -> Result<u32, String> {
+        self.merge_albums_tx(keep_id, merge_id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+    async fn split_album(
+        &self,
+        album_id: &str,
+        track_ids: &[String],
+        new_title: &str,
+    ) -> Result<Album, String> {
+        let new_id = self
+            .split_album_tx(album_id, track_ids, new_title)
+            .await
+            .map_err(|e| e.to_string())?;
+        self.get_album(&new_id).await
+    }
+    async fn get_tracks_by_format(
+        &self,
+        codec: Option<&str>,
+        min_bitrate: Option<u32>,
+    ) -> Result<Vec<Track>, String> {
+        let rows = sqlx::query(
+            r#"SELECT t.*, a.name as artist_name, al.title as album_title
+            FROM tracks t
+            LEFT JOIN artists a ON t.artist_id = a.id
+            LEFT JOIN albums al ON t.album_id = al.id
+            WHERE (?1 IS NULL OR t.codec = UPPER(?1))
+            AND (?2 IS NULL OR t.bitrate >= ?2)
+            ORDER BY t.bitrate DESC, t.title COLLATE NOCASE ASC"#,
+        )
+        .bind(codec)
+        .bind(min_bitrate)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
+    }
+    async fn get_lossless_tracks(&self) -> Result<Vec<Track>, String> {
+        let placeholders = vec!["?"; LOSSLESS_CODECS.len()].join(", ");
+        let sql = format!(
+            r#"SELECT t.*, a.name as artist_name, al.title as album_title
+            FROM tracks t
+            LEFT JOIN artists a ON t.artist_id = a.id
+            LEFT JOIN albums al ON t.album_id = al.id
+            WHERE t.codec IN ({})
+            ORDER BY COALESCE(a.sort_name, a.name) COLLATE NOCASE ASC,
+                COALESCE(al.sort_name, al.title) COLLATE NOCASE ASC, t.track_number ASC"#,
+            placeholders
+        );
+        let mut query = sqlx::query(&sql);
+        for codec in LOSSLESS_CODECS {
+            query = query.bind(codec);
+        }
+        let rows = query.fetch_all(&self.db).await.map_err(|e| e.to_string())?;
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
+    }
+    async fn is_favorite(&self, track_id: &str) -> Result<bool, String> {
+        let liked: Option<bool> = sqlx::query_scalar("SELECT liked FROM tracks WHERE id = ?")
+            .bind(track_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        liked.ok_or("Track not found".to_string())
+    }
     async fn get_playlists(&self) -> Result<Vec<Playlist>, String> {
-        let rows = sqlx::query(r#"SELECT p.*, (SELECT COUNT(*) FROM playlist_tracks WHERE playlist_id = p.id) as track_count FROM playlists p ORDER BY created_at DESC"#).fetch_all(&self.db).await.map_err(|e| e.to_string())?;
+        let rows = sqlx::query(r#"SELECT p.id, p.name, p.owner, p.created_at, COALESCE(p.cover_art, (SELECT al.cover_art FROM playlist_tracks pt JOIN tracks t ON pt.track_id = t.id JOIN albums al ON t.album_id = al.id WHERE pt.playlist_id = p.id AND al.cover_art IS NOT NULL ORDER BY pt.position ASC LIMIT 1)) as cover_art, (SELECT COUNT(*) FROM playlist_tracks WHERE playlist_id = p.id) as track_count FROM playlists p ORDER BY created_at DESC"#).fetch_all(&self.db).await.map_err(|e| e.to_string())?;
         Ok(rows
             .into_iter()
             .map(|row| Playlist {
@@ -1006,10 +3354,28 @@ impl LibraryProvider for LocalProvider {
                 owner: row.try_get("owner").unwrap_or_default(),
                 track_count: row.try_get::<i64, _>("track_count").unwrap_or(0) as u32,
                 cover_art: row.try_get("cover_art").ok(),
+                description: None,
                 created_at: row.try_get("created_at").unwrap_or_default(),
             })
             .collect())
     }
+    async fn get_playlist(&self, id: &str) -> Result<Playlist, String> {
+        let row = sqlx::query(r#"SELECT p.id, p.name, p.owner, p.created_at, COALESCE(p.cover_art, (SELECT al.cover_art FROM playlist_tracks pt JOIN tracks t ON pt.track_id = t.id JOIN albums al ON t.album_id = al.id WHERE pt.playlist_id = p.id AND al.cover_art IS NOT NULL ORDER BY pt.position ASC LIMIT 1)) as cover_art, (SELECT COUNT(*) FROM playlist_tracks WHERE playlist_id = p.id) as track_count FROM playlists p WHERE p.id = ?"#)
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("Playlist not found".to_string())?;
+        Ok(Playlist {
+            id: row.get("id"),
+            name: row.get("name"),
+            owner: row.try_get("owner").unwrap_or_default(),
+            track_count: row.try_get::<i64, _>("track_count").unwrap_or(0) as u32,
+            cover_art: row.try_get("cover_art").ok(),
+            description: None,
+            created_at: row.try_get("created_at").unwrap_or_default(),
+        })
+    }
     async fn create_playlist(&self, name: &str) -> Result<Playlist, String> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = Utc::now();
@@ -1026,6 +3392,7 @@ impl LibraryProvider for LocalProvider {
             owner: "local".to_string(),
             track_count: 0,
             cover_art: None,
+            description: None,
             created_at: now,
         })
     }
@@ -1037,6 +3404,50 @@ impl LibraryProvider for LocalProvider {
             .map_err(|e| e.to_string())?;
         Ok(())
     }
+    async fn rename_playlist(&self, id: &str, name: &str) -> Result<(), String> {
+        let res = sqlx::query("UPDATE playlists SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        if res.rows_affected() == 0 {
+            return Err("Playlist not found".to_string());
+        }
+        Ok(())
+    }
+    async fn set_playlist_cover(&self, id: &str, image_path: &str) -> Result<(), String> {
+        let data = fs::read(image_path).map_err(|e| e.to_string())?;
+        let mime_type = match Path::new(image_path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .as_deref()
+        {
+            Some("png") => "image/png",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            _ => "image/jpeg",
+        };
+        let filename = save_cover_art(
+            &self.data_dir.join(COVERS_DIR),
+            &CoverImageData {
+                data,
+                mime_type: mime_type.to_string(),
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+        let res = sqlx::query("UPDATE playlists SET cover_art = ? WHERE id = ?")
+            .bind(filename)
+            .bind(id)
+            .execute(&self.db)
+            .await
+            .map_err(|e| e.to_string())?;
+        if res.rows_affected() == 0 {
+            return Err("Playlist not found".to_string());
+        }
+        Ok(())
+    }
     async fn add_to_playlist(&self, playlist_id: &str, track_id: &str) -> Result<(), String> {
         let row = sqlx::query(
             "SELECT MAX(position) as max_pos FROM playlist_tracks WHERE playlist_id = ?",
@@ -1058,46 +3469,120 @@ impl LibraryProvider for LocalProvider {
             .map_err(|e| e.to_string())?;
         Ok(())
     }
+    async fn remove_from_playlist_at(&self, playlist_id: &str, index: u32) -> Result<(), String> {
+        let res = sqlx::query(
+            r#"DELETE FROM playlist_tracks WHERE rowid = (
+                SELECT pt.rowid FROM playlist_tracks pt JOIN tracks t ON pt.track_id = t.id
+                WHERE pt.playlist_id = ? ORDER BY pt.position ASC LIMIT 1 OFFSET ?
+            )"#,
+        )
+        .bind(playlist_id)
+        .bind(index)
+        .execute(&self.db)
+        .await
+        .map_err(|e| e.to_string())?;
+        if res.rows_affected() == 0 {
+            return Err("Playlist entry not found".to_string());
+        }
+        Ok(())
+    }
     async fn resolve_stream(&self, track_id: &str) -> Result<AudioStream, String> {
-        let row = sqlx::query("SELECT path FROM tracks WHERE id = ?")
-            .bind(track_id)
-            .fetch_optional(&self.db)
-            .await
-            .map_err(|e| e.to_string())?
-            .ok_or("Track not found".to_string())?;
-        Ok(AudioStream::Url(row.get("path")))
+        let row = sqlx::query(
+            "SELECT path, media_path, start_offset, end_offset FROM tracks WHERE id = ?",
+        )
+        .bind(track_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("Track not found".to_string())?;
+        Ok(match row.get::<Option<String>, _>("media_path") {
+            Some(url) => AudioStream::Segment {
+                url,
+                start: row.get::<Option<f64>, _>("start_offset").unwrap_or(0.0),
+                end: row.get("end_offset"),
+            },
+            None => AudioStream::Url(row.get("path")),
+        })
     }
     async fn scan(&self) -> Result<(), String> {
+        self.scan_cancel.store(false, Ordering::SeqCst);
         let rows = sqlx::query("SELECT path FROM library_roots")
             .fetch_all(&self.db)
             .await
             .map_err(|e| e.to_string())?;
-        let existing_tracks_rows = sqlx::query("SELECT path, mtime FROM tracks")
-            .fetch_all(&self.db)
+        self.reset_mtimes_if_charset_changed()
             .await
             .map_err(|e| e.to_string())?;
-        let mut existing_map: HashMap<PathBuf, i64> =
-            HashMap::with_capacity(existing_tracks_rows.len());
-        for row in existing_tracks_rows {
-            let p: String = row.get("path");
-            let m: i64 = row.try_get("mtime").unwrap_or(0);
-            existing_map.insert(PathBuf::from(p), m);
-        }
-        let existing_map_arc = Arc::new(existing_map);
+        let roots: Vec<String> = rows.iter().map(|row| row.get("path")).collect();
+        let skipped_aliases = Arc::new(
+            self.symlink_aliases(roots)
+                .await
+                .map_err(|e| e.to_string())?,
+        );
         sqlx::query("DELETE FROM scan_found")
             .execute(&self.db)
             .await
             .map_err(|e| e.to_string())?;
+        let mut failures = Vec::new();
         for row in rows {
             let path: String = row.get("path");
-            if let Err(e) = self.scan_path(&path, existing_map_arc.clone()).await {
+            let res = match self.existing_mtimes(&path).await {
+                Ok(existing) => {
+                    self.scan_path(&path, Arc::new(existing), skipped_aliases.clone())
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = res {
                 log::error!("Scan failed for root {}: {}", path, e);
-                return Err(format!("Scan failed for root {}: {}", path, e));
+                let _ = ScanRootFailed {
+                    provider_id: self.id.clone(),
+                    root: path.clone(),
+                    error: e.to_string(),
+                }
+                .emit(&self.app);
+
+                // Keep the failed root's tracks out of the prune below.
+                let prefix = dir_prefix(&path);
+                let _ = sqlx::query(
+                    "INSERT OR IGNORE INTO scan_found (path) SELECT path FROM tracks WHERE path >= ? AND path < ?",
+                )
+                .bind(&prefix)
+                .bind(format!("{}\u{10FFFF}", prefix))
+                .execute(&self.db)
+                .await;
+                failures.push(format!("{}: {}", path, e));
+            }
+            if self.scan_cancel.load(Ordering::SeqCst) {
+                break;
+            }
+        }
+        if self.scan_cancel.load(Ordering::SeqCst) {
+            // Tracks under roots (or folders) not reached yet aren't in scan_found,
+            // so skip the prune and keep them until a full scan runs.
+            log::info!("Scan of {} cancelled", self.id);
+            let _ = ScanCancelled {
+                provider_id: self.id.clone(),
+            }
+            .emit(&self.app);
+            let _ = LibraryChanged {
+                provider_id: self.id.clone(),
+                kind: LibraryChangeKind::TracksAdded,
             }
+            .emit(&self.app);
+            return Err("Scan cancelled".to_string());
         }
         let _ = sqlx::query("DELETE FROM tracks WHERE path NOT IN (SELECT path FROM scan_found)")
             .execute(&self.db)
             .await;
+        match self.merge_disc_split_albums().await {
+            Ok(0) => {}
+            Ok(n) => log::info!("Merged {} albums split by disc suffix", n),
+            Err(e) => log::warn!("Failed to merge disc-split albums: {}", e),
+        }
+        if let Err(e) = self.refresh_sort_names().await {
+            log::warn!("Failed to refresh sort names: {}", e);
+        }
         let _ = sqlx::query("PRAGMA optimize").execute(&self.db).await;
 
         if let Some(lastfm_config) = &self.config.lastfm_session {
@@ -1108,8 +3593,8 @@ impl LibraryProvider for LocalProvider {
                     Some(lastfm_config.session_key.clone()),
                 );
 
-                let artists: Vec<(String, String)> = sqlx::query_as(
-                    "SELECT id, name FROM artists WHERE bio IS NULL OR image_url IS NULL",
+                let artists: Vec<(String, String, Option<String>)> = sqlx::query_as(
+                    "SELECT id, name, mbid FROM artists WHERE bio IS NULL OR image_url IS NULL",
                 )
                 .fetch_all(&self.db)
                 .await
@@ -1117,20 +3602,45 @@ impl LibraryProvider for LocalProvider {
 
                 let client = Arc::new(client);
                 let db_pool = self.db.clone();
+                let bio_sql = if self.config.overwrite_artist_bio {
+                    "COALESCE(?, bio)"
+                } else {
+                    "COALESCE(bio, ?)"
+                };
+                let image_sql = if self.config.prefer_local_art {
+                    "COALESCE(image_url, ?)"
+                } else {
+                    "COALESCE(?, image_url)"
+                };
+                let update_sql = format!(
+                    "UPDATE artists SET bio = {}, image_url = {} WHERE id = ?",
+                    bio_sql, image_sql
+                );
 
                 futures::stream::iter(artists)
-                    .map(|(id, name)| {
+                    .map(|(id, name, mbid)| {
                         let client = client.clone();
                         let db_pool = db_pool.clone();
+                        let update_sql = update_sql.clone();
                         async move {
-                            if name == "Unknown Artist" {
+                            if name == UNKNOWN_ARTIST {
                                 return;
                             }
 
                             let mut attempts = 0;
                             loop {
-                                match client.get_artist_info(&name).await {
+                                match client.get_artist_info(&name, mbid.as_deref()).await {
                                     Ok(info) => {
+                                        if mbid.is_none() {
+                                            if let Some(found) = info.mbid.as_deref().filter(|m| !m.is_empty()) {
+                                                let _ = sqlx::query("UPDATE artists SET mbid = ? WHERE id = ? AND mbid IS NULL")
+                                                    .bind(found)
+                                                    .bind(&id)
+                                                    .execute(&db_pool)
+                                                    .await;
+                                            }
+                                        }
+
                                         let mut bio = None;
                                         let mut image_url = None;
 
@@ -1151,7 +3661,7 @@ impl LibraryProvider for LocalProvider {
                                         }
 
                                         if bio.is_some() || image_url.is_some() {
-                                            let _ = sqlx::query("UPDATE artists SET bio = COALESCE(?, bio), image_url = COALESCE(?, image_url) WHERE id = ?")
+                                            let _ = sqlx::query(&update_sql)
                                                 .bind(bio)
                                                 .bind(image_url)
                                                 .bind(&id)
@@ -1186,9 +3696,32 @@ impl LibraryProvider for LocalProvider {
                     .buffer_unordered(20)
                     .collect::<Vec<()>>()
                     .await;
+
+                log::info!("Fetching album metadata...");
+                if let Err(e) = enrich_albums(&self.db, client).await {
+                    log::warn!("Album metadata enrichment failed: {}", e);
+                }
             }
         }
 
+        let _ = LibraryChanged {
+            provider_id: self.id.clone(),
+            kind: LibraryChangeKind::TracksAdded,
+        }
+        .emit(&self.app);
+
+        if !failures.is_empty() {
+            return Err(format!(
+                "Scan failed for {} root(s): {}",
+                failures.len(),
+                failures.join("; ")
+            ));
+        }
+        Ok(())
+    }
+
+    async fn cancel_scan(&self) -> Result<(), String> {
+        self.scan_cancel.store(true, Ordering::SeqCst);
         Ok(())
     }
 
@@ -1202,10 +3735,7 @@ impl LibraryProvider for LocalProvider {
     }
     async fn get_playlist_tracks(&self, playlist_id: &str) -> Result<Vec<Track>, String> {
         let rows = sqlx::query(r#"SELECT t.*, a.name as artist_name, al.title as album_title FROM playlist_tracks pt JOIN tracks t ON pt.track_id = t.id LEFT JOIN artists a ON t.artist_id = a.id LEFT JOIN albums al ON t.album_id = al.id WHERE pt.playlist_id = ? ORDER BY pt.position ASC"#).bind(playlist_id).fetch_all(&self.db).await.map_err(|e| e.to_string())?;
-        Ok(rows
-            .into_iter()
-            .map(|r| map_row_to_track(r, Some(self.id.clone())))
-            .collect())
+        Ok(rows.into_iter().map(|r| self.map_track(r)).collect())
     }
 }
 
@@ -1242,7 +3772,215 @@ fn map_row_to_track(row: sqlx::sqlite::SqliteRow, provider_id: Option<String>) -
         year: row.try_get("year").ok(),
         genre: row.try_get("genre").ok(),
         bitrate: row.try_get("bitrate").ok(),
+        codec: row.try_get("codec").ok().flatten(),
+        peak: row
+            .try_get::<Option<f64>, _>("peak")
+            .ok()
+            .flatten()
+            .map(|p| p as f32),
         play_count: row.try_get("play_count").unwrap_or(0),
         liked: row.try_get("liked").unwrap_or(false),
+        explicit: row.try_get("explicit").unwrap_or(false),
+        rating: row
+            .try_get::<Option<u8>, _>("rating")
+            .ok()
+            .flatten()
+            .filter(|r| *r > 0),
+        skip_count: row.try_get("skip_count").unwrap_or(0),
+        playlist_position: None,
+        gain_db: row.try_get("gain_db").unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leading_digits_in_artist_are_not_a_track_number() {
+        let (number, parts) = split_filename_stem("3 Doors Down - Kryptonite");
+        assert_eq!(number, None);
+        assert_eq!(parts, ["3 Doors Down", "Kryptonite"]);
+    }
+
+    #[test]
+    fn leading_digits_in_title_are_not_a_track_number() {
+        let (number, parts) = split_filename_stem("99 Luftballons");
+        assert_eq!(number, None);
+        assert_eq!(parts, ["99 Luftballons"]);
+    }
+
+    #[test]
+    fn track_number_is_split_off() {
+        assert_eq!(
+            split_filename_stem("01 - Artist - Title"),
+            (Some(1), vec!["Artist", "Title"])
+        );
+        assert_eq!(split_filename_stem("3. Title"), (Some(3), vec!["Title"]));
+        assert_eq!(
+            split_filename_stem("Artist - 07_Title"),
+            (Some(7), vec!["Artist", "Title"])
+        );
+    }
+
+    fn disc_suffixes() -> Vec<String> {
+        DEFAULT_DISC_SUFFIXES
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn strips_disc_suffixes() {
+        let suffixes = disc_suffixes();
+        assert_eq!(
+            strip_disc_suffix("Album (Disc 2)", &suffixes),
+            Some(("Album".to_string(), 2))
+        );
+        assert_eq!(
+            strip_disc_suffix("Album CD2", &suffixes),
+            Some(("Album".to_string(), 2))
+        );
+        assert_eq!(
+            strip_disc_suffix("Album - Disc Two", &suffixes),
+            Some(("Album".to_string(), 2))
+        );
+    }
+
+    #[test]
+    fn keeps_titles_without_a_disc_marker() {
+        let suffixes = disc_suffixes();
+        assert_eq!(strip_disc_suffix("Discovery", &suffixes), None);
+        assert_eq!(strip_disc_suffix("Album (Live)", &suffixes), None);
+        assert_eq!(strip_disc_suffix("Album (Disc 2)", &[]), None);
+    }
+
+    #[test]
+    fn parses_disc_markers() {
+        let suffixes = disc_suffixes();
+        assert_eq!(parse_disc_marker("Disc 2", &suffixes), Some(2));
+        assert_eq!(parse_disc_marker("CD2", &suffixes), Some(2));
+        assert_eq!(parse_disc_marker("disk two", &suffixes), Some(2));
+        assert_eq!(parse_disc_marker("Disc 1 of 2", &suffixes), Some(1));
+        assert_eq!(parse_disc_marker("Disc 0", &suffixes), None);
+        assert_eq!(parse_disc_marker("Live", &suffixes), None);
+    }
+
+    #[tokio::test]
+    async fn album_tracks_put_unnumbered_tracks_after_numbered_ones() {
+        use sqlx::{Connection, SqliteConnection};
+
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE tracks (title TEXT, path TEXT, disc_number INTEGER, track_number INTEGER)",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        for (title, path, disc, number) in [
+            ("bonus", "/a/bonus.flac", Some(1), None),
+            ("Second", "/a/02.flac", Some(1), Some(2)),
+            ("Alpha", "/a/alpha.flac", None, None),
+            ("Disc Two", "/a/2-01.flac", Some(2), Some(1)),
+            ("First", "/a/01.flac", None, Some(1)),
+        ] {
+            sqlx::query(
+                "INSERT INTO tracks (title, path, disc_number, track_number) VALUES (?, ?, ?, ?)",
+            )
+            .bind(title)
+            .bind(path)
+            .bind(disc)
+            .bind(number)
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+
+        let titles: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT t.title FROM tracks t ORDER BY {}",
+            ALBUM_TRACK_ORDER
+        ))
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(titles, ["First", "Second", "Alpha", "bonus", "Disc Two"]);
+    }
+
+    #[test]
+    fn blank_queries_have_no_search_pattern() {
+        assert_eq!(search_pattern(""), None);
+        assert_eq!(search_pattern("   "), None);
+    }
+
+    #[test]
+    fn single_character_queries_match_as_a_prefix() {
+        assert_eq!(search_pattern("a"), Some("a%".to_string()));
+        assert_eq!(search_pattern(" é "), Some("é%".to_string()));
+        assert_eq!(search_pattern("ab"), Some("%ab%".to_string()));
+        assert_eq!(
+            search_pattern(" daft punk "),
+            Some("%daft punk%".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_cue_times() {
+        assert_eq!(parse_cue_time("00:00:00"), Some(0.0));
+        assert_eq!(parse_cue_time("03:25:15"), Some(205.2));
+        assert_eq!(parse_cue_time("74:59:74"), Some(4499.0 + 74.0 / 75.0));
+        assert_eq!(parse_cue_time("03:25"), None);
+        assert_eq!(parse_cue_time("03:xx:00"), None);
+    }
+
+    #[test]
+    fn parses_cue_sheets() {
+        let text = "\u{feff}REM GENRE \"Electronic\"\r
+REM DATE 2001-03-12\r
+PERFORMER \"Daft Punk\"\r
+TITLE \"Discovery\"\r
+FILE \"Daft Punk - Discovery.flac\" WAVE\r
+  TRACK 01 AUDIO\r
+    TITLE \"One More Time\"\r
+    INDEX 01 00:00:00\r
+  TRACK 02 AUDIO\r
+    TITLE \"Aerodynamic\"\r
+    PERFORMER \"Thomas Bangalter\"\r
+    INDEX 00 05:19:70\r
+    INDEX 01 05:20:30\r
+FILE \"empty.flac\" WAVE\r
+";
+        let sheet = parse_cue_sheet(text, Path::new("/music"));
+
+        assert_eq!(sheet.title.as_deref(), Some("Discovery"));
+        assert_eq!(sheet.performer.as_deref(), Some("Daft Punk"));
+        assert_eq!(sheet.genre.as_deref(), Some("Electronic"));
+        assert_eq!(sheet.year, Some(2001));
+        assert_eq!(sheet.files.len(), 1);
+        let file = &sheet.files[0];
+        assert_eq!(file.path, Path::new("/music/Daft Punk - Discovery.flac"));
+        assert_eq!(file.tracks.len(), 2);
+        assert_eq!(file.tracks[0].title.as_deref(), Some("One More Time"));
+        assert_eq!(file.tracks[0].performer, None);
+        assert_eq!(file.tracks[1].number, 2);
+        assert_eq!(
+            file.tracks[1].performer.as_deref(),
+            Some("Thomas Bangalter")
+        );
+        assert_eq!(file.tracks[1].start, 320.4);
+    }
+
+    #[test]
+    fn decodes_cue_sheets_in_legacy_code_pages() {
+        assert_eq!(
+            decode_cue_text(b"\xEF\xBB\xBFTITLE \"Caf\xC3\xA9\"", None),
+            "TITLE \"Caf\u{e9}\""
+        );
+        assert_eq!(
+            decode_cue_text(
+                b"TITLE \"\xCF\xF0\xE8\xE2\xE5\xF2\"",
+                Some(&TagCharset::Fixed(encoding_rs::WINDOWS_1251))
+            ),
+            "TITLE \"\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442}\""
+        );
     }
 }
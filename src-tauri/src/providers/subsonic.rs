@@ -1,14 +1,19 @@
+use crate::models::config::CoverArtSize;
 use crate::models::entities::{
-    Album, Artist, Genre, LibraryStats, Playlist, Track, UnifiedSearchResult,
+    Album, Artist, ConnectionTestResult, Genre, LibraryStats, Lyrics, LyricsSource,
+    NowPlayingEntry, Playlist, PodcastChannel, ProviderCapabilities, SearchType, Track,
+    UnifiedSearchResult,
 };
-use crate::traits::{AudioStream, LibraryProvider};
+use crate::traits::{AudioStream, LibraryProvider, REQUEST_TIMEOUT_ERROR};
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use moka::future::Cache;
 use reqwest::{Client, Url};
 use serde::Deserialize;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::Semaphore;
 
 #[derive(Clone)]
 pub struct SubsonicProvider {
@@ -21,6 +26,15 @@ pub struct SubsonicProvider {
     client: Client,
 
     cache: Cache<String, String>,
+    request_limiter: Arc<Semaphore>,
+    request_timeout: Duration,
+    cover_art_px: u32,
+}
+
+pub struct SubsonicOptions {
+    pub max_concurrent_requests: u32,
+    pub request_timeout_secs: u64,
+    pub cover_art_size: CoverArtSize,
 }
 
 impl SubsonicProvider {
@@ -31,6 +45,7 @@ impl SubsonicProvider {
         username: String,
         token: String,
         salt: String,
+        options: SubsonicOptions,
     ) -> Result<Self> {
         let cache = Cache::builder()
             .max_capacity(500)
@@ -45,10 +60,18 @@ impl SubsonicProvider {
             token,
             salt,
             client: Client::builder()
-                .timeout(Duration::from_secs(30))
                 .build()
                 .context("Failed to build HTTP client")?,
             cache,
+            request_limiter: Arc::new(Semaphore::new(
+                options.max_concurrent_requests.max(1) as usize
+            )),
+            request_timeout: Duration::from_secs(options.request_timeout_secs.max(1)),
+            cover_art_px: match options.cover_art_size {
+                CoverArtSize::Thumb => 150,
+                CoverArtSize::Medium => 600,
+                CoverArtSize::Large => 1500,
+            },
         })
     }
 
@@ -62,14 +85,24 @@ impl SubsonicProvider {
     fn should_cache(&self, endpoint: &str) -> bool {
         match endpoint {
             "star" | "unstar" | "scrobble" | "startScan" | "getScanStatus" | "getRandomSongs"
-            | "stream" => false,
+            | "stream" | "updatePlaylist" | "getNowPlaying" | "setRating" | "ping"
+            | "getSimilarSongs" => false,
 
             _ => true,
         }
     }
 
+    /// Drops cached playlist responses after the playlist was changed.
+    async fn invalidate_playlist(&self, playlist_id: &str) {
+        self.cache.remove(&self.build_url("getPlaylists")).await;
+
+        let mut playlist_url = self.build_url("getPlaylist");
+        playlist_url.push_str(&format!("&id={}", playlist_id));
+        self.cache.remove(&playlist_url).await;
+    }
+
     fn get_cover_art_url(&self, id: &str) -> String {
-        self.build_url("getCoverArt") + &format!("&id={}&size=600", id)
+        self.build_url("getCoverArt") + &format!("&id={}&size={}", id, self.cover_art_px)
     }
 
     fn map_err(e: anyhow::Error) -> String {
@@ -99,7 +132,24 @@ impl SubsonicProvider {
         }
 
         let url = Url::parse(&url_str).context("Invalid URL constructed")?;
-        let res = self.client.get(url).send().await?;
+        let _permit = self
+            .request_limiter
+            .acquire()
+            .await
+            .context("Request limiter closed")?;
+        let res = self
+            .client
+            .get(url)
+            .timeout(self.request_timeout)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    anyhow!(REQUEST_TIMEOUT_ERROR)
+                } else {
+                    e.into()
+                }
+            })?;
 
         if !res.status().is_success() {
             let status = res.status();
@@ -127,6 +177,63 @@ impl SubsonicProvider {
         Ok(response.response.content)
     }
 
+    async fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let url = Url::parse(url).context("Invalid URL constructed")?;
+        let _permit = self
+            .request_limiter
+            .acquire()
+            .await
+            .context("Request limiter closed")?;
+        let res = self
+            .client
+            .get(url)
+            .timeout(self.request_timeout)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    anyhow!(REQUEST_TIMEOUT_ERROR)
+                } else {
+                    e.into()
+                }
+            })?;
+        if !res.status().is_success() {
+            return Err(anyhow!("Subsonic request failed: HTTP {}", res.status()));
+        }
+        Ok(res.bytes().await?.to_vec())
+    }
+
+    pub async fn test_connection(&self) -> ConnectionTestResult {
+        match self.request::<PingResponse>("ping", &[]).await {
+            Ok(ping) => {
+                let server_version = ping.server_version.or(ping.version);
+                let message = match &ping.server_type {
+                    Some(server) => format!("Connected to {}", server),
+                    None => "Connected".to_string(),
+                };
+                ConnectionTestResult {
+                    ok: true,
+                    server_version,
+                    message,
+                }
+            }
+            Err(e) => ConnectionTestResult {
+                ok: false,
+                server_version: None,
+                message: e.to_string(),
+            },
+        }
+    }
+
+    fn map_artist(&self, sub: SubsonicArtistID3) -> Artist {
+        Artist {
+            id: sub.id,
+            name: sub.name,
+            bio: None,
+            image_url: sub.cover_art.map(|id| self.get_cover_art_url(&id)),
+        }
+    }
+
     fn map_album(&self, sub: SubsonicAlbum) -> Album {
         Album {
             id: sub.id,
@@ -154,8 +261,52 @@ impl SubsonicProvider {
             year: sub.year.map(|y| y as u16),
             genre: sub.genre,
             bitrate: sub.bitrate,
+            codec: sub.suffix.map(|s| s.to_uppercase()),
+            peak: sub.replay_gain.and_then(|rg| rg.track_peak),
             play_count: sub.play_count.unwrap_or(0),
             liked: sub.starred.is_some(),
+            explicit: sub.explicit_status.as_deref() == Some("explicit"),
+            rating: sub.user_rating.filter(|r| *r > 0),
+            skip_count: 0,
+            playlist_position: None,
+            gain_db: None,
+        }
+    }
+
+    fn map_podcast_channel(&self, sub: SubsonicPodcastChannel) -> PodcastChannel {
+        PodcastChannel {
+            id: sub.id,
+            title: sub.title.unwrap_or_else(|| "Untitled Podcast".to_string()),
+            description: sub.description,
+            cover_art: sub.cover_art.map(|id| self.get_cover_art_url(&id)),
+            url: sub.url,
+        }
+    }
+
+    fn map_podcast_episode(&self, sub: SubsonicPodcastEpisode, channel_title: &str) -> Track {
+        Track {
+            id: sub.stream_id.unwrap_or(sub.id),
+            provider_id: Some(self.id.clone()),
+            title: sub.title,
+            artist_id: String::new(),
+            artist_name: sub.artist.unwrap_or_else(|| channel_title.to_string()),
+            album_id: sub.channel_id.unwrap_or_default(),
+            album_title: sub.album.unwrap_or_else(|| channel_title.to_string()),
+            duration_sec: sub.duration.unwrap_or(0),
+            track_number: None,
+            disc_number: None,
+            year: sub.year.map(|y| y as u16),
+            genre: sub.genre,
+            bitrate: sub.bitrate,
+            codec: None,
+            peak: None,
+            play_count: 0,
+            liked: false,
+            explicit: false,
+            rating: None,
+            skip_count: 0,
+            playlist_position: None,
+            gain_db: None,
         }
     }
 
@@ -171,6 +322,7 @@ impl SubsonicProvider {
             track_count: sub.song_count,
             cover_art: sub.cover_art.map(|id| self.get_cover_art_url(&id)),
             owner: sub.owner.unwrap_or_else(|| "Unknown".to_string()),
+            description: sub.comment,
             created_at,
         }
     }
@@ -186,6 +338,23 @@ impl LibraryProvider for SubsonicProvider {
         &self.name
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            can_rename_playlist: true,
+            can_remove_from_playlist: true,
+            writable_favorites: true,
+            can_rate: true,
+            has_radio: true,
+            can_scan: true,
+            has_podcasts: true,
+            ..Default::default()
+        }
+    }
+
+    async fn is_online(&self) -> bool {
+        self.test_connection().await.ok
+    }
+
     async fn get_recent_albums(&self, limit: u32) -> Result<Vec<Album>, String> {
         let limit_str = limit.to_string();
         let res: AlbumList2Response = self
@@ -328,15 +497,24 @@ impl LibraryProvider for SubsonicProvider {
         Ok(tracks)
     }
 
-    async fn search(&self, query: &str) -> Result<UnifiedSearchResult, String> {
+    async fn search(
+        &self,
+        query: &str,
+        types: &[SearchType],
+    ) -> Result<UnifiedSearchResult, String> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(UnifiedSearchResult::default());
+        }
+        let count = |t: SearchType| if types.contains(&t) { "20" } else { "0" };
         let res: Search3Response = self
             .request(
                 "search3",
                 &[
                     ("query", query),
-                    ("songCount", "20"),
-                    ("albumCount", "20"),
-                    ("artistCount", "20"),
+                    ("songCount", count(SearchType::Tracks)),
+                    ("albumCount", count(SearchType::Albums)),
+                    ("artistCount", count(SearchType::Artists)),
                 ],
             )
             .await
@@ -351,15 +529,7 @@ impl LibraryProvider for SubsonicProvider {
             result.albums = albums.into_iter().map(|a| self.map_album(a)).collect();
         }
         if let Some(artists) = res.result.artist {
-            result.artists = artists
-                .into_iter()
-                .map(|a| Artist {
-                    id: a.id.clone(),
-                    name: a.name,
-                    bio: None,
-                    image_url: a.cover_art.map(|id| self.get_cover_art_url(&id)),
-                })
-                .collect();
+            result.artists = artists.into_iter().map(|a| self.map_artist(a)).collect();
         }
         Ok(result)
     }
@@ -395,6 +565,45 @@ impl LibraryProvider for SubsonicProvider {
         Ok(artist)
     }
 
+    async fn get_artist_related(&self, artist_id: &str) -> Result<Vec<Artist>, String> {
+        let res: GetArtistInfoResponse = self
+            .request("getArtistInfo", &[("id", artist_id)])
+            .await
+            .map_err(Self::map_err)?;
+
+        let artists = res
+            .artist_info
+            .similar_artist
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| self.map_artist(a))
+            .collect();
+        Ok(artists)
+    }
+
+    async fn get_cover_image(&self, album_id: &str) -> Result<Vec<u8>, String> {
+        let album = self.get_album(album_id).await?;
+        let url = album.cover_art.ok_or("Album has no cover".to_string())?;
+        self.fetch_bytes(&url).await.map_err(Self::map_err)
+    }
+
+    async fn get_similar_tracks(&self, track_id: &str, count: u32) -> Result<Vec<Track>, String> {
+        let count = count.to_string();
+        let res: SimilarSongsResponse = self
+            .request("getSimilarSongs", &[("id", track_id), ("count", &count)])
+            .await
+            .map_err(Self::map_err)?;
+
+        let tracks: Vec<Track> = res
+            .songs
+            .song
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| self.map_track(s))
+            .collect();
+        Ok(tracks)
+    }
+
     async fn get_album(&self, id: &str) -> Result<Album, String> {
         let res: GetAlbumResponse = self
             .request("getAlbum", &[("id", id)])
@@ -419,6 +628,14 @@ impl LibraryProvider for SubsonicProvider {
         Ok(albums)
     }
 
+    async fn prefetch_album(&self, album_id: &str) -> Result<(), String> {
+        // get_album and get_album_tracks share this cached response.
+        self.request::<GetAlbumResponse>("getAlbum", &[("id", album_id)])
+            .await
+            .map(|_| ())
+            .map_err(Self::map_err)
+    }
+
     async fn get_album_tracks(&self, album_id: &str) -> Result<Vec<Track>, String> {
         let res: GetAlbumResponse = self
             .request("getAlbum", &[("id", album_id)])
@@ -444,6 +661,13 @@ impl LibraryProvider for SubsonicProvider {
         Ok(self.map_track(res.song))
     }
 
+    async fn refresh_track(&self, track_id: &str) -> Result<Track, String> {
+        let mut song_url = self.build_url("getSong");
+        song_url.push_str(&format!("&id={}", track_id));
+        self.cache.remove(&song_url).await;
+        self.get_track(track_id).await
+    }
+
     async fn set_track_liked(&self, track_id: &str, liked: bool) -> Result<(), String> {
         let endpoint = if liked { "star" } else { "unstar" };
 
@@ -462,6 +686,54 @@ impl LibraryProvider for SubsonicProvider {
         Ok(())
     }
 
+    async fn get_lyrics(
+        &self,
+        track_id: &str,
+        source: LyricsSource,
+    ) -> Result<Option<Lyrics>, String> {
+        if source != LyricsSource::Provider {
+            return Ok(None);
+        }
+
+        // OpenSubsonic servers have structured, possibly synced, lyrics by id.
+        // Plain Subsonic servers fail the call, so fall back to the artist/title lookup.
+        if let Ok(res) = self
+            .request::<LyricsListResponse>("getLyricsBySongId", &[("id", track_id)])
+            .await
+        {
+            let list = res.lyrics_list.structured_lyrics.unwrap_or_default();
+            if let Some(lyrics) = list.iter().find(|l| l.synced).or(list.first()) {
+                return Ok(Some(lyrics.to_lyrics()));
+            }
+        }
+
+        let track = self.get_track(track_id).await?;
+        let artist = urlencoding::encode(&track.artist_name);
+        let title = urlencoding::encode(&track.title);
+        let res: LyricsResponse = self
+            .request("getLyrics", &[("artist", &artist), ("title", &title)])
+            .await
+            .map_err(Self::map_err)?;
+        Ok(res
+            .lyrics
+            .and_then(|l| l.value)
+            .and_then(|text| Lyrics::from_text(LyricsSource::Provider, text)))
+    }
+
+    async fn set_track_rating(&self, track_id: &str, rating: u8) -> Result<(), String> {
+        let rating = rating.to_string();
+        let _: serde_json::Value = self
+            .request("setRating", &[("id", track_id), ("rating", &rating)])
+            .await
+            .map_err(Self::map_err)?;
+
+        let mut song_url = self.build_url("getSong");
+        song_url.push_str(&format!("&id={}", track_id));
+        self.cache.remove(&song_url).await;
+
+        Ok(())
+    }
+
     async fn resolve_stream(&self, track_id: &str) -> Result<AudioStream, String> {
         let stream_url = self.build_url("stream") + &format!("&id={}", track_id);
         Ok(AudioStream::Url(stream_url))
@@ -484,6 +756,43 @@ impl LibraryProvider for SubsonicProvider {
         Ok(playlists)
     }
 
+    async fn get_playlist(&self, playlist_id: &str) -> Result<Playlist, String> {
+        let res: GetPlaylistResponse = self
+            .request("getPlaylist", &[("id", playlist_id)])
+            .await
+            .map_err(Self::map_err)?;
+
+        Ok(self.map_playlist(res.playlist.info))
+    }
+
+    async fn rename_playlist(&self, playlist_id: &str, name: &str) -> Result<(), String> {
+        let encoded_name = urlencoding::encode(name);
+        let _: serde_json::Value = self
+            .request(
+                "updatePlaylist",
+                &[("playlistId", playlist_id), ("name", &encoded_name)],
+            )
+            .await
+            .map_err(Self::map_err)?;
+        self.invalidate_playlist(playlist_id).await;
+        Ok(())
+    }
+
+    async fn remove_from_playlist_at(&self, playlist_id: &str, index: u32) -> Result<(), String> {
+        let _: serde_json::Value = self
+            .request(
+                "updatePlaylist",
+                &[
+                    ("playlistId", playlist_id),
+                    ("songIndexToRemove", &index.to_string()),
+                ],
+            )
+            .await
+            .map_err(Self::map_err)?;
+        self.invalidate_playlist(playlist_id).await;
+        Ok(())
+    }
+
     async fn get_playlist_tracks(&self, playlist_id: &str) -> Result<Vec<Track>, String> {
         let res: GetPlaylistResponse = self
             .request("getPlaylist", &[("id", playlist_id)])
@@ -501,6 +810,71 @@ impl LibraryProvider for SubsonicProvider {
         Ok(tracks)
     }
 
+    async fn get_podcasts(&self) -> Result<Vec<PodcastChannel>, String> {
+        let res: GetPodcastsResponse = self
+            .request("getPodcasts", &[("includeEpisodes", "false")])
+            .await
+            .map_err(Self::map_err)?;
+
+        let channels = res
+            .podcasts
+            .channel
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| self.map_podcast_channel(c))
+            .collect();
+        Ok(channels)
+    }
+
+    async fn get_podcast_episodes(&self, channel_id: &str) -> Result<Vec<Track>, String> {
+        let res: GetPodcastsResponse = self
+            .request(
+                "getPodcasts",
+                &[("id", channel_id), ("includeEpisodes", "true")],
+            )
+            .await
+            .map_err(Self::map_err)?;
+
+        let channel = res
+            .podcasts
+            .channel
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .ok_or("Podcast channel not found".to_string())?;
+        let channel_title = channel.title.clone().unwrap_or_default();
+
+        let episodes = channel
+            .episode
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| e.status.as_deref().is_none_or(|s| s == "completed"))
+            .map(|e| self.map_podcast_episode(e, &channel_title))
+            .collect();
+        Ok(episodes)
+    }
+
+    async fn get_server_now_playing(&self) -> Result<Vec<NowPlayingEntry>, String> {
+        let res: NowPlayingResponse = self
+            .request("getNowPlaying", &[])
+            .await
+            .map_err(Self::map_err)?;
+
+        let entries = res
+            .now_playing
+            .entry
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| NowPlayingEntry {
+                username: e.username,
+                player_name: e.player_name,
+                minutes_ago: e.minutes_ago.unwrap_or(0),
+                track: self.map_track(e.song),
+            })
+            .collect();
+        Ok(entries)
+    }
+
     async fn scan(&self) -> Result<(), String> {
         self.cache.invalidate_all();
         Ok(())
@@ -528,6 +902,15 @@ struct SubsonicError {
     message: String,
 }
 
+#[derive(Deserialize)]
+struct PingResponse {
+    version: Option<String>,
+    #[serde(rename = "serverVersion")]
+    server_version: Option<String>,
+    #[serde(rename = "type")]
+    server_type: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct GetArtistResponse {
     artist: SubsonicArtistDetail,
@@ -554,6 +937,8 @@ struct SubsonicArtistInfo {
     biography: Option<String>,
     #[serde(rename = "largeImageUrl")]
     large_image_url: Option<String>,
+    #[serde(rename = "similarArtist")]
+    similar_artist: Option<Vec<SubsonicArtistID3>>,
 }
 
 #[derive(Deserialize)]
@@ -616,9 +1001,22 @@ struct SubsonicSong {
     duration: Option<u32>,
     #[serde(rename = "bitRate")]
     bitrate: Option<u32>,
+    suffix: Option<String>,
     #[serde(rename = "playCount")]
     play_count: Option<u32>,
     starred: Option<String>,
+    #[serde(rename = "explicitStatus")]
+    explicit_status: Option<String>,
+    #[serde(rename = "userRating")]
+    user_rating: Option<u8>,
+    #[serde(rename = "replayGain")]
+    replay_gain: Option<SubsonicReplayGain>,
+}
+
+#[derive(Deserialize)]
+struct SubsonicReplayGain {
+    #[serde(rename = "trackPeak")]
+    track_peak: Option<f32>,
 }
 
 #[derive(Deserialize)]
@@ -632,6 +1030,81 @@ struct SongsByGenreResponse {
     songs: SongsContainer,
 }
 
+#[derive(Deserialize)]
+struct LyricsResponse {
+    lyrics: Option<SubsonicLyrics>,
+}
+
+#[derive(Deserialize)]
+struct SubsonicLyrics {
+    value: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LyricsListResponse {
+    #[serde(rename = "lyricsList")]
+    lyrics_list: LyricsList,
+}
+
+#[derive(Deserialize)]
+struct LyricsList {
+    #[serde(rename = "structuredLyrics")]
+    structured_lyrics: Option<Vec<StructuredLyrics>>,
+}
+
+#[derive(Deserialize)]
+struct StructuredLyrics {
+    #[serde(default)]
+    synced: bool,
+    #[serde(default)]
+    line: Vec<LyricsLine>,
+}
+
+#[derive(Deserialize)]
+struct LyricsLine {
+    /// Milliseconds from the start of the track; only set when synced.
+    start: Option<u64>,
+    value: String,
+}
+
+impl StructuredLyrics {
+    fn to_lyrics(&self) -> Lyrics {
+        let plain = self
+            .line
+            .iter()
+            .map(|l| l.value.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let synced = self.synced.then(|| {
+            self.line
+                .iter()
+                .map(|l| {
+                    let ms = l.start.unwrap_or(0);
+                    format!(
+                        "[{:02}:{:02}.{:02}]{}",
+                        ms / 60_000,
+                        ms / 1000 % 60,
+                        ms / 10 % 100,
+                        l.value
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+        Lyrics {
+            source: LyricsSource::Provider,
+            synced,
+            plain: Some(plain),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SimilarSongsResponse {
+    #[serde(rename = "similarSongs")]
+    songs: SongsContainer,
+}
+
 #[derive(Deserialize)]
 struct SongsContainerResponse {
     #[serde(rename = "randomSongs")]
@@ -662,6 +1135,7 @@ struct SubsonicPlaylist {
     #[serde(rename = "coverArt")]
     cover_art: Option<String>,
     owner: Option<String>,
+    comment: Option<String>,
     created: Option<String>,
 }
 
@@ -673,10 +1147,32 @@ struct GetPlaylistResponse {
 #[derive(Deserialize)]
 struct SubsonicPlaylistDetail {
     #[serde(flatten)]
-    _info: SubsonicPlaylist,
+    info: SubsonicPlaylist,
     entry: Option<Vec<SubsonicSong>>,
 }
 
+#[derive(Deserialize)]
+struct NowPlayingResponse {
+    #[serde(rename = "nowPlaying")]
+    now_playing: NowPlayingContainer,
+}
+
+#[derive(Deserialize)]
+struct NowPlayingContainer {
+    entry: Option<Vec<SubsonicNowPlayingEntry>>,
+}
+
+#[derive(Deserialize)]
+struct SubsonicNowPlayingEntry {
+    #[serde(flatten)]
+    song: SubsonicSong,
+    username: String,
+    #[serde(rename = "minutesAgo")]
+    minutes_ago: Option<u32>,
+    #[serde(rename = "playerName")]
+    player_name: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct Search3Response {
     #[serde(rename = "searchResult3")]
@@ -735,3 +1231,42 @@ struct ScanStatusResponse {
 struct SubsonicScanStatus {
     count: Option<u32>,
 }
+
+#[derive(Deserialize)]
+struct GetPodcastsResponse {
+    podcasts: PodcastsContainer,
+}
+
+#[derive(Deserialize)]
+struct PodcastsContainer {
+    channel: Option<Vec<SubsonicPodcastChannel>>,
+}
+
+#[derive(Deserialize)]
+struct SubsonicPodcastChannel {
+    id: String,
+    url: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    #[serde(rename = "coverArt")]
+    cover_art: Option<String>,
+    episode: Option<Vec<SubsonicPodcastEpisode>>,
+}
+
+#[derive(Deserialize)]
+struct SubsonicPodcastEpisode {
+    id: String,
+    #[serde(rename = "streamId")]
+    stream_id: Option<String>,
+    #[serde(rename = "channelId")]
+    channel_id: Option<String>,
+    title: String,
+    artist: Option<String>,
+    album: Option<String>,
+    year: Option<i32>,
+    genre: Option<String>,
+    duration: Option<u32>,
+    #[serde(rename = "bitRate")]
+    bitrate: Option<u32>,
+    status: Option<String>,
+}
@@ -1,19 +1,67 @@
 use crate::models::{
-    entities::{PlayerEvent, Track},
-    player::{PersistedPlayer, PersistedQueue, PersistedState, Queue, RepeatMode},
+    config::EndOfQueueBehavior,
+    entities::{PlaybackFailure, PlayerEvent, Track, TrackChanged},
+    player::{
+        NavigationState, PersistedPlayer, PersistedQueue, PersistedState, PlayerState, Queue,
+        RepeatMode,
+    },
 };
-use crate::traits::{AudioEngine, LibraryProvider};
+use crate::traits::{AudioEngine, AudioStream, LibraryProvider};
 use rand::seq::SliceRandom;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex, RwLock};
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(3);
+const POSITION_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_RECENT_FAILURES: usize = 50;
 
 pub struct QueueManager {
     state: Mutex<QueueState>,
     pub player: Box<dyn AudioEngine>,
     providers: Arc<RwLock<HashMap<String, Arc<dyn LibraryProvider>>>>,
     state_path: PathBuf,
+    /// Set while `load_state` restores the last session. Player events emitted by the
+    /// restore are synthetic, so scrobbling and Discord presence ignore them until the
+    /// user starts playback themselves.
+    restoring: AtomicBool,
+    hide_explicit: AtomicBool,
+    /// Set by mutations; the saver task flushes the state file at most every
+    /// `SAVE_INTERVAL` instead of writing on every change.
+    dirty: AtomicBool,
+    /// Set on time updates. Position-only saves reuse `persisted_queue` so the
+    /// queue isn't rebuilt every few seconds while a track plays.
+    position_dirty: AtomicBool,
+    persisted_queue: Mutex<Option<PersistedQueue>>,
+    /// Set while a preview snippet is playing. Loading any queue track cancels the
+    /// preview; `preview_generation` stops a stale timer from restoring over a newer one.
+    previewing: AtomicBool,
+    preview_generation: AtomicU64,
+    preview_resume: Mutex<Option<PlayerState>>,
+    /// Set by `stop()`. The current index is kept so `play()` can reload the track,
+    /// but the engine has nothing loaded until then.
+    stopped: AtomicBool,
+    end_of_queue: std::sync::Mutex<EndOfQueueBehavior>,
+    /// One-shot request to stop once the current track ends; cleared when it fires.
+    stop_after_current: AtomicBool,
+    /// Most recent playback failures, newest last, capped at `MAX_RECENT_FAILURES`.
+    recent_failures: std::sync::Mutex<VecDeque<PlaybackFailure>>,
+    /// Track whose stream was already reloaded after an error, so a track that
+    /// keeps failing isn't retried in a loop. Cleared when a track ends normally.
+    stream_retry: std::sync::Mutex<Option<String>>,
+    /// Provider tried first when a track id comes without one.
+    default_provider: std::sync::Mutex<Option<String>>,
+    /// Order `ordered_providers` returns providers in.
+    source_order: std::sync::Mutex<Vec<String>>,
+    track_events: broadcast::Sender<TrackChanged>,
+    /// Set when the engine reports the current track finished, cleared when another
+    /// one loads. `next()` while it's unset counts as a skip.
+    track_ended: AtomicBool,
+    auto_skip_threshold: std::sync::Mutex<Option<u32>>,
+    max_queue_size: std::sync::Mutex<Option<u32>>,
 }
 
 #[derive(Default)]
@@ -40,6 +88,50 @@ impl QueueManager {
             player,
             providers: providers.clone(),
             state_path,
+            restoring: AtomicBool::new(false),
+            hide_explicit: AtomicBool::new(false),
+            dirty: AtomicBool::new(false),
+            position_dirty: AtomicBool::new(false),
+            persisted_queue: Mutex::new(None),
+            previewing: AtomicBool::new(false),
+            preview_generation: AtomicU64::new(0),
+            preview_resume: Mutex::new(None),
+            stopped: AtomicBool::new(false),
+            end_of_queue: std::sync::Mutex::new(EndOfQueueBehavior::default()),
+            stop_after_current: AtomicBool::new(false),
+            recent_failures: std::sync::Mutex::new(VecDeque::new()),
+            stream_retry: std::sync::Mutex::new(None),
+            default_provider: std::sync::Mutex::new(None),
+            source_order: std::sync::Mutex::new(Vec::new()),
+            track_events: broadcast::channel(16).0,
+            track_ended: AtomicBool::new(false),
+            auto_skip_threshold: std::sync::Mutex::new(None),
+            max_queue_size: std::sync::Mutex::new(None),
+        });
+
+        let qm_saver = qm.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SAVE_INTERVAL);
+            let mut last_save = Instant::now();
+            loop {
+                interval.tick().await;
+                if qm_saver.previewing.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let res = if qm_saver.dirty.load(Ordering::SeqCst) {
+                    qm_saver.save().await
+                } else if qm_saver.position_dirty.load(Ordering::SeqCst)
+                    && last_save.elapsed() >= POSITION_SAVE_INTERVAL
+                {
+                    qm_saver.save_position().await
+                } else {
+                    continue;
+                };
+                last_save = Instant::now();
+                if let Err(e) = res {
+                    log::error!("Failed to save playback state: {}", e);
+                }
+            }
         });
 
         let qm_clone = qm.clone();
@@ -47,15 +139,32 @@ impl QueueManager {
             let mut rx = qm_clone.player.subscribe();
             while let Ok(event) = rx.recv().await {
                 match event {
-                    PlayerEvent::Paused | PlayerEvent::Playing | PlayerEvent::Ended => {
-                        let _ = qm_clone.save().await;
+                    PlayerEvent::Paused
+                    | PlayerEvent::Playing
+                    | PlayerEvent::Stopped
+                    | PlayerEvent::Ended => {
+                        qm_clone.mark_dirty();
+                    }
+                    PlayerEvent::TimeUpdate(_) => {
+                        qm_clone.position_dirty.store(true, Ordering::SeqCst);
                     }
-                    PlayerEvent::TimeUpdate(_) => {}
                     PlayerEvent::DurationChange(_) => {}
-                    PlayerEvent::Error(_) => {}
+                    PlayerEvent::Buffering(_) | PlayerEvent::BufferProgress(_) => {}
+                    PlayerEvent::Error(ref e) => {
+                        if let Some(track) = qm_clone.current_track().await {
+                            qm_clone.record_failure(&track, e);
+                        }
+                        qm_clone.retry_current_stream().await;
+                    }
+                    PlayerEvent::EngineRestarted => {}
+                    PlayerEvent::EngineLog(_) => {}
                 }
 
                 if let PlayerEvent::Ended = event {
+                    qm_clone.track_ended.store(true, Ordering::SeqCst);
+                    if let Ok(mut retried) = qm_clone.stream_retry.lock() {
+                        *retried = None;
+                    }
                     let _ = qm_clone.on_playback_ended().await;
                 }
             }
@@ -64,73 +173,347 @@ impl QueueManager {
         qm
     }
 
-    pub async fn load_state(&self) {
+    pub async fn load_state(&self, default_volume: f32) {
+        if !self.state_path.exists() {
+            let _ = self.player.set_volume(default_volume).await;
+        }
         if let Ok(content) = std::fs::read_to_string(&self.state_path) {
             if let Ok(persisted) = serde_json::from_str::<PersistedState>(&content) {
-                let pq = persisted.queue;
-                let mut tracks = Vec::new();
-                for id in &pq.tracks {
-                    if let Some(track) = self.get_track(id).await {
-                        tracks.push(track);
-                    }
-                }
+                self.restore(persisted).await;
+            }
+        }
+    }
 
-                let mut current_track_to_load = None;
-                let mut state = self.state.lock().await;
-                if tracks.len() == pq.tracks.len() {
-                    state.tracks = tracks;
-                    state.current_index = pq.current_index;
-                    state.repeat_mode = pq.repeat_mode;
-                    state.shuffle = pq.shuffle;
-                    state.shuffled_indices = pq.shuffled_indices;
-                } else {
-                    state.tracks = tracks;
-                    state.repeat_mode = pq.repeat_mode;
-                    if pq.shuffle {
-                        recalc_shuffle(&mut state);
-                    }
-                }
+    /// Replaces the queue with `persisted` and loads its current track paused
+    /// at the saved position.
+    async fn restore(&self, persisted: PersistedState) {
+        let pq = persisted.queue;
+        let mut tracks = Vec::new();
+        for (i, id) in pq.tracks.iter().enumerate() {
+            let provider_id = pq.track_providers.get(i).cloned().flatten();
+            if let Some(track) = self.get_track(id, provider_id.as_deref()).await {
+                tracks.push(track);
+            }
+        }
 
-                if let Some(idx) = state.current_index {
-                    current_track_to_load = state.tracks.get(idx).cloned();
-                }
+        let mut current_track_to_load = None;
+        let mut state = self.state.lock().await;
+        if tracks.len() == pq.tracks.len() {
+            state.tracks = tracks;
+            state.current_index = pq.current_index;
+            state.repeat_mode = pq.repeat_mode;
+            state.shuffle = pq.shuffle;
+            state.shuffled_indices = pq.shuffled_indices;
+        } else {
+            state.tracks = tracks;
+            state.current_index = None;
+            state.repeat_mode = pq.repeat_mode;
+            state.shuffle = pq.shuffle;
+            if pq.shuffle {
+                recalc_shuffle(&mut state, self.auto_skip_threshold());
+            } else {
+                state.shuffled_indices.clear();
+            }
+        }
 
-                drop(state);
-                let _ = self.player.set_volume(persisted.player.volume).await;
+        if let Some(idx) = state.current_index {
+            current_track_to_load = state.tracks.get(idx).cloned();
+        }
 
-                if let Some(track) = current_track_to_load {
-                    if let Ok(_) = self.load_track(&track, false).await {
-                        let _ = self.player.seek(persisted.player.position).await;
-                    }
-                }
+        drop(state);
+        let _ = self.player.set_volume(persisted.player.volume).await;
+
+        if let Some(track) = current_track_to_load {
+            self.restoring.store(true, Ordering::SeqCst);
+            if let Ok(_) = self.load_track(&track, false).await {
+                let _ = self.player.seek(persisted.player.position).await;
             }
         }
     }
 
+    fn sessions_path(&self) -> PathBuf {
+        self.state_path.with_file_name("sessions.json")
+    }
+
+    /// A missing file means no sessions were saved yet. Any other failure is
+    /// returned, so a corrupt or newer-format file is never overwritten.
+    fn read_sessions(&self) -> Result<BTreeMap<String, PersistedState>, String> {
+        let path = self.sessions_path();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    async fn write_sessions(
+        &self,
+        sessions: BTreeMap<String, PersistedState>,
+    ) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&sessions).map_err(|e| e.to_string())?;
+        let path = self.sessions_path();
+        tokio::task::spawn_blocking(move || std::fs::write(&path, json))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())
+    }
+
+    /// Stores the current queue and position under `name`, replacing any
+    /// session already saved with that name.
+    pub async fn save_session(&self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Session name cannot be empty".to_string());
+        }
+        let persisted = self.persisted_state(self.snapshot_queue().await).await;
+        let mut sessions = self.read_sessions()?;
+        sessions.insert(name.to_string(), persisted);
+        self.write_sessions(sessions).await
+    }
+
+    pub fn list_sessions(&self) -> Result<Vec<String>, String> {
+        Ok(self.read_sessions()?.into_keys().collect())
+    }
+
+    pub async fn load_session(&self, name: &str) -> Result<(), String> {
+        let persisted = self
+            .read_sessions()?
+            .remove(name)
+            .ok_or("Session not found".to_string())?;
+        self.restore(persisted).await;
+        if self.current_track().await.is_none() {
+            self.player.stop().await?;
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    pub async fn delete_session(&self, name: &str) -> Result<(), String> {
+        let mut sessions = self.read_sessions()?;
+        if sessions.remove(name).is_none() {
+            return Err("Session not found".to_string());
+        }
+        self.write_sessions(sessions).await
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Resumes playback, reloading the current track if playback was stopped.
+    pub async fn play(&self) -> Result<(), String> {
+        self.end_restore();
+        if self.is_stopped() {
+            if let Some(track) = self.current_track().await {
+                return self.load_track(&track, true).await;
+            }
+        }
+        self.player.play().await
+    }
+
+    /// Stops the engine and unloads the track while keeping the queue intact.
+    pub async fn stop(&self) -> Result<(), String> {
+        self.player.stop().await?;
+        self.stopped.store(true, Ordering::SeqCst);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    /// Re-resolves the current track once after a playback error, so a stream
+    /// URL that expired while paused gets a fresh one instead of dead air.
+    async fn retry_current_stream(&self) {
+        let Some(track) = self.current_track().await else {
+            return;
+        };
+        if let Ok(mut retried) = self.stream_retry.lock() {
+            if retried.as_deref() == Some(track.id.as_str()) {
+                return;
+            }
+            *retried = Some(track.id.clone());
+        }
+        log::info!("Reloading stream for {} after playback error", track.id);
+        let previous = self.player.get_state().await;
+        if let Err(e) = self.reload_current(&previous).await {
+            log::warn!("Failed to reload stream for {}: {}", track.id, e);
+        }
+    }
+
+    /// Reloads the current track on the engine, resuming from `previous`. Used
+    /// after the audio backend has been swapped out from under the queue, and to
+    /// recover a stream that stopped working.
+    pub async fn reload_current(&self, previous: &PlayerState) -> Result<(), String> {
+        let _ = self.player.set_volume(previous.volume).await;
+        if previous.muted {
+            let _ = self.player.toggle_mute(Some(true)).await;
+        }
+        if self.is_stopped() {
+            return Ok(());
+        }
+        let Some(track) = self.current_track().await else {
+            return Ok(());
+        };
+        self.load_stream(&track, !previous.paused).await?;
+        if previous.position > 0.0 {
+            let _ = self.player.seek(previous.position).await;
+        }
+        Ok(())
+    }
+
+    pub fn is_restoring(&self) -> bool {
+        self.restoring.load(Ordering::SeqCst)
+    }
+
+    pub fn end_restore(&self) {
+        self.restoring.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether a preview snippet is playing, or the queue track is being reloaded
+    /// after one. Player events in that window don't belong to the queue track.
+    pub fn is_previewing(&self) -> bool {
+        self.previewing.load(Ordering::SeqCst)
+    }
+
+    pub fn hides_explicit(&self) -> bool {
+        self.hide_explicit.load(Ordering::SeqCst)
+    }
+
+    pub fn set_end_of_queue(&self, behavior: EndOfQueueBehavior) {
+        if let Ok(mut current) = self.end_of_queue.lock() {
+            *current = behavior;
+        }
+    }
+
+    pub fn set_auto_skip_threshold(&self, threshold: Option<u32>) {
+        if let Ok(mut current) = self.auto_skip_threshold.lock() {
+            *current = threshold;
+        }
+    }
+
+    fn auto_skip_threshold(&self) -> Option<u32> {
+        self.auto_skip_threshold.lock().ok().and_then(|t| *t)
+    }
+
+    pub fn set_max_queue_size(&self, size: Option<u32>) {
+        if let Ok(mut current) = self.max_queue_size.lock() {
+            *current = size;
+        }
+    }
+
+    fn max_queue_size(&self) -> Option<u32> {
+        self.max_queue_size.lock().ok().and_then(|s| *s)
+    }
+
+    pub fn set_source_order(&self, order: Vec<String>) {
+        if let Ok(mut current) = self.source_order.lock() {
+            *current = order;
+        }
+    }
+
+    pub fn set_default_provider(&self, provider_id: Option<String>) {
+        if let Ok(mut current) = self.default_provider.lock() {
+            *current = provider_id;
+        }
+    }
+
+    fn end_of_queue(&self) -> EndOfQueueBehavior {
+        self.end_of_queue.lock().map(|b| *b).unwrap_or_default()
+    }
+
+    fn record_failure(&self, track: &Track, error: &str) {
+        if let Ok(mut failures) = self.recent_failures.lock() {
+            if failures.len() >= MAX_RECENT_FAILURES {
+                failures.pop_front();
+            }
+            failures.push_back(PlaybackFailure {
+                track_id: track.id.clone(),
+                track_title: track.title.clone(),
+                provider_id: track.provider_id.clone(),
+                error: error.to_string(),
+                timestamp: chrono::Utc::now(),
+            });
+        }
+    }
+
+    /// Recent playback failures, newest first.
+    pub fn recent_failures(&self) -> Vec<PlaybackFailure> {
+        self.recent_failures
+            .lock()
+            .map(|f| f.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn clear_recent_failures(&self) {
+        if let Ok(mut failures) = self.recent_failures.lock() {
+            failures.clear();
+        }
+    }
+
+    pub fn set_stop_after_current(&self, enabled: bool) {
+        self.stop_after_current.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn set_hide_explicit(&self, hide: bool) {
+        self.hide_explicit.store(hide, Ordering::SeqCst);
+    }
+
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
     pub async fn save(&self) -> Result<(), String> {
+        self.dirty.store(false, Ordering::SeqCst);
+        let queue = self.snapshot_queue().await;
+        *self.persisted_queue.lock().await = Some(queue.clone());
+        self.write_state(queue).await
+    }
+
+    async fn snapshot_queue(&self) -> PersistedQueue {
         let state = self.state.lock().await;
-        let player_state = self.player.get_state().await;
+        PersistedQueue {
+            tracks: state.tracks.clone().iter().map(|t| t.id.clone()).collect(),
+            track_providers: state.tracks.iter().map(|t| t.provider_id.clone()).collect(),
+            current_index: state.current_index,
+            repeat_mode: state.repeat_mode.clone(),
+            shuffle: state.shuffle,
+            shuffled_indices: state.shuffled_indices.clone(),
+        }
+    }
 
-        let persisted = PersistedState {
-            queue: PersistedQueue {
-                tracks: state.tracks.clone().iter().map(|t| t.id.clone()).collect(),
-                current_index: state.current_index,
-                repeat_mode: state.repeat_mode.clone(),
-                shuffle: state.shuffle,
-                shuffled_indices: state.shuffled_indices.clone(),
-            },
+    async fn save_position(&self) -> Result<(), String> {
+        let queue = self.persisted_queue.lock().await.clone();
+        match queue {
+            Some(queue) => self.write_state(queue).await,
+            None => self.save().await,
+        }
+    }
+
+    async fn persisted_state(&self, queue: PersistedQueue) -> PersistedState {
+        let player_state = self.player.get_state().await;
+        PersistedState {
+            queue,
             player: PersistedPlayer {
                 volume: player_state.volume,
                 position: player_state.position,
             },
-        };
+        }
+    }
 
+    async fn write_state(&self, queue: PersistedQueue) -> Result<(), String> {
+        self.position_dirty.store(false, Ordering::SeqCst);
+        let persisted = self.persisted_state(queue).await;
         let json = serde_json::to_string_pretty(&persisted).map_err(|e| e.to_string())?;
-        if let Some(parent) = self.state_path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        std::fs::write(&self.state_path, json).map_err(|e| e.to_string())?;
-        Ok(())
+        let path = self.state_path.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            std::fs::write(&path, json)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
     }
 
     pub async fn get_provider(&self, id: &str) -> Option<Arc<dyn LibraryProvider>> {
@@ -141,9 +524,48 @@ impl QueueManager {
         self.providers.read().await.clone()
     }
 
-    pub async fn get_track(&self, track_id: &str) -> Option<Track> {
-        let providers = self.providers.read().await;
-        for provider in providers.values() {
+    /// All providers in the configured source order, for commands that combine
+    /// their results. Providers missing from the order come last, by id.
+    pub async fn ordered_providers(&self) -> Vec<Arc<dyn LibraryProvider>> {
+        let order = self
+            .source_order
+            .lock()
+            .map(|o| o.clone())
+            .unwrap_or_default();
+        let mut providers: Vec<Arc<dyn LibraryProvider>> =
+            self.providers.read().await.values().cloned().collect();
+        providers.sort_by_cached_key(|p| {
+            (
+                order
+                    .iter()
+                    .position(|id| id == p.id())
+                    .unwrap_or(order.len()),
+                p.id().to_string(),
+            )
+        });
+        providers
+    }
+
+    /// All providers, with the configured default provider first.
+    async fn providers_by_preference(&self) -> Vec<Arc<dyn LibraryProvider>> {
+        let preferred = self.default_provider.lock().ok().and_then(|p| p.clone());
+        let mut providers: Vec<Arc<dyn LibraryProvider>> =
+            self.providers.read().await.values().cloned().collect();
+        if let Some(preferred) = preferred {
+            providers.sort_by_key(|p| p.id() != preferred);
+        }
+        providers
+    }
+
+    /// Looks a track up in `provider_id` only when given, since providers can
+    /// use overlapping id schemes. Without one, the default provider is tried
+    /// first and then the first other provider that knows the id wins.
+    pub async fn get_track(&self, track_id: &str, provider_id: Option<&str>) -> Option<Track> {
+        if let Some(pid) = provider_id {
+            let provider = self.get_provider(pid).await?;
+            return provider.get_track(track_id).await.ok();
+        }
+        for provider in self.providers_by_preference().await {
             if let Ok(track) = provider.get_track(track_id).await {
                 return Some(track);
             }
@@ -151,6 +573,54 @@ impl QueueManager {
         None
     }
 
+    /// Swaps queued copies of `track` for the given one, e.g. after refetching it
+    /// with full metadata.
+    pub async fn update_track(&self, track: &Track) {
+        let mut state = self.state.lock().await;
+        let mut changed = false;
+        for queued in state
+            .tracks
+            .iter_mut()
+            .filter(|t| t.id == track.id && t.provider_id == track.provider_id)
+        {
+            *queued = track.clone();
+            changed = true;
+        }
+        drop(state);
+        if changed {
+            self.mark_dirty();
+        }
+    }
+
+    pub async fn get_tracks(&self, track_ids: &[String], provider_id: Option<&str>) -> Vec<Track> {
+        let providers: Vec<Arc<dyn LibraryProvider>> = match provider_id {
+            Some(pid) => self.get_provider(pid).await.into_iter().collect(),
+            None => self.providers_by_preference().await,
+        };
+
+        let mut found: HashMap<String, Track> = HashMap::new();
+        for provider in providers {
+            let missing: Vec<String> = track_ids
+                .iter()
+                .filter(|id| !found.contains_key(*id))
+                .cloned()
+                .collect();
+            if missing.is_empty() {
+                break;
+            }
+            if let Ok(tracks) = provider.get_tracks(&missing).await {
+                for track in tracks {
+                    found.insert(track.id.clone(), track);
+                }
+            }
+        }
+
+        track_ids
+            .iter()
+            .filter_map(|id| found.get(id).cloned())
+            .collect()
+    }
+
     pub async fn add_provider(&self, provider: Arc<dyn LibraryProvider>) {
         self.providers
             .write()
@@ -188,7 +658,7 @@ impl QueueManager {
             state.current_index = None;
             state.shuffled_indices.clear();
         } else if state.shuffle {
-            recalc_shuffle(&mut state);
+            recalc_shuffle(&mut state, self.auto_skip_threshold());
         }
 
         drop(state);
@@ -196,7 +666,7 @@ impl QueueManager {
         if was_playing_removed {
             let _ = self.player.stop().await;
         }
-        let _ = self.save().await;
+        self.mark_dirty();
     }
 
     pub async fn add_track(&self, track: Track) {
@@ -206,19 +676,22 @@ impl QueueManager {
             let len = state.tracks.len();
             state.shuffled_indices.push(len - 1);
         }
+        evict_played(&mut state, self.max_queue_size());
         drop(state);
-        let _ = self.save().await;
+        self.mark_dirty();
     }
 
     pub async fn add_tracks(&self, tracks: Vec<Track>) {
         let mut state = self.state.lock().await;
+        let start = state.tracks.len();
         state.tracks.extend(tracks);
         if state.shuffle {
             let len = state.tracks.len();
-            state.shuffled_indices.push(len - 1);
+            state.shuffled_indices.extend(start..len);
         }
+        evict_played(&mut state, self.max_queue_size());
         drop(state);
-        let _ = self.save().await;
+        self.mark_dirty();
     }
 
     pub async fn add_next(&self, track: Track) {
@@ -238,8 +711,31 @@ impl QueueManager {
                 state.shuffled_indices.push(len - 1);
             }
         }
+        evict_played(&mut state, self.max_queue_size());
         drop(state);
-        let _ = self.save().await;
+        self.mark_dirty();
+    }
+
+    pub async fn insert(&self, track: Track, index: usize) {
+        let mut state = self.state.lock().await;
+        let index = index.min(state.tracks.len());
+        state.tracks.insert(index, track);
+        if let Some(curr) = state.current_index {
+            if index <= curr {
+                state.current_index = Some(curr + 1);
+            }
+        }
+        if state.shuffle {
+            for idx in state.shuffled_indices.iter_mut() {
+                if *idx >= index {
+                    *idx += 1;
+                }
+            }
+            state.shuffled_indices.push(index);
+        }
+        evict_played(&mut state, self.max_queue_size());
+        drop(state);
+        self.mark_dirty();
     }
 
     pub async fn remove(&self, index: usize) {
@@ -247,7 +743,12 @@ impl QueueManager {
         if index < state.tracks.len() {
             state.tracks.remove(index);
             if state.shuffle {
-                recalc_shuffle(&mut state);
+                state.shuffled_indices.retain(|&i| i != index);
+                for idx in state.shuffled_indices.iter_mut() {
+                    if *idx > index {
+                        *idx -= 1;
+                    }
+                }
             }
 
             if let Some(curr) = state.current_index {
@@ -263,7 +764,7 @@ impl QueueManager {
             }
         }
         drop(state);
-        let _ = self.save().await;
+        self.mark_dirty();
     }
 
     pub async fn clear(&self) {
@@ -272,20 +773,27 @@ impl QueueManager {
         state.shuffled_indices.clear();
         state.current_index = None;
         drop(state);
-        let _ = self.save().await;
+        self.mark_dirty();
+    }
+
+    pub async fn trim_played(&self) {
+        let mut state = self.state.lock().await;
+        remove_played(&mut state, usize::MAX);
+        drop(state);
+        self.mark_dirty();
     }
 
     pub async fn toggle_shuffle(&self) -> bool {
         let mut state = self.state.lock().await;
         state.shuffle = !state.shuffle;
         if state.shuffle {
-            recalc_shuffle(&mut state);
+            recalc_shuffle(&mut state, self.auto_skip_threshold());
         } else {
             state.shuffled_indices.clear();
         }
         let res = state.shuffle;
         drop(state);
-        let _ = self.save().await;
+        self.mark_dirty();
         res
     }
 
@@ -293,7 +801,7 @@ impl QueueManager {
         let mut state = self.state.lock().await;
         state.repeat_mode = mode;
         drop(state);
-        let _ = self.save().await;
+        self.mark_dirty();
     }
 
     pub async fn play_now(&self, track: Track) -> Result<(), String> {
@@ -308,7 +816,68 @@ impl QueueManager {
         drop(state);
 
         let res = self.load_track(&track, true).await;
-        let _ = self.save().await;
+        self.mark_dirty();
+        res
+    }
+
+    /// Plays `track` right away while keeping the rest of the queue: it goes in
+    /// after the current track and becomes current, so `next()` carries on with
+    /// what was queued.
+    pub async fn play_in_queue(&self, track: Track) -> Result<(), String> {
+        let mut state = self.state.lock().await;
+        let index = state.current_index.map_or(state.tracks.len(), |c| c + 1);
+        state.tracks.insert(index, track.clone());
+        if state.shuffle {
+            let pos = state
+                .current_index
+                .and_then(|c| state.shuffled_indices.iter().position(|&i| i == c))
+                .map_or(state.shuffled_indices.len(), |p| p + 1);
+            for idx in state.shuffled_indices.iter_mut() {
+                if *idx >= index {
+                    *idx += 1;
+                }
+            }
+            state.shuffled_indices.insert(pos, index);
+        }
+        state.current_index = Some(index);
+        evict_played(&mut state, self.max_queue_size());
+        drop(state);
+
+        let res = self.load_track(&track, true).await;
+        self.mark_dirty();
+        res
+    }
+
+    pub async fn set_queue(
+        &self,
+        tracks: Vec<Track>,
+        start_index: usize,
+        autoplay: bool,
+    ) -> Result<(), String> {
+        if !tracks.is_empty() && start_index >= tracks.len() {
+            return Err("Index out of bounds".to_string());
+        }
+
+        let mut state = self.state.lock().await;
+        state.tracks = tracks;
+        state.current_index = if state.tracks.is_empty() {
+            None
+        } else {
+            Some(start_index)
+        };
+        if state.shuffle {
+            recalc_shuffle(&mut state, self.auto_skip_threshold());
+        } else {
+            state.shuffled_indices.clear();
+        }
+        let current = state.current_index.map(|idx| state.tracks[idx].clone());
+        drop(state);
+
+        let res = match current {
+            Some(track) => self.load_track(&track, autoplay).await,
+            None => self.player.stop().await,
+        };
+        self.mark_dirty();
         res
     }
 
@@ -319,14 +888,80 @@ impl QueueManager {
             let track = state.tracks[index].clone();
             drop(state);
             let res = self.load_track(&track, true).await;
-            let _ = self.save().await;
+            self.mark_dirty();
             res
         } else {
             Err("Index out of bounds".to_string())
         }
     }
 
+    pub async fn preview(
+        self: &Arc<Self>,
+        stream: AudioStream,
+        start_sec: f64,
+        duration_sec: f64,
+    ) -> Result<(), String> {
+        if !self.previewing.swap(true, Ordering::SeqCst) {
+            *self.preview_resume.lock().await = Some(self.player.get_state().await);
+        }
+        let generation = self.preview_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        if let Err(e) = self.player.load(stream, true).await {
+            self.end_preview().await;
+            return Err(e);
+        }
+        if start_sec > 0.0 {
+            let _ = self.player.seek(start_sec).await;
+        }
+
+        let qm = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs_f64(duration_sec.max(0.0))).await;
+            if !qm.previewing.load(Ordering::SeqCst)
+                || qm.preview_generation.load(Ordering::SeqCst) != generation
+            {
+                return;
+            }
+            qm.end_preview().await;
+        });
+        Ok(())
+    }
+
+    /// Reloads the queue track the preview interrupted. `previewing` stays set
+    /// until it's back, so the reload isn't reported as a new play.
+    async fn end_preview(&self) {
+        let resume = self.preview_resume.lock().await.take();
+        let current = if self.is_stopped() {
+            None
+        } else {
+            self.current_track().await
+        };
+        match current {
+            Some(track) => {
+                if self.resolve_and_load(&track, false).await.is_ok() {
+                    self.track_ended.store(false, Ordering::SeqCst);
+                    if let Some(prev) = resume {
+                        let _ = self.player.seek(prev.position).await;
+                        if !prev.paused {
+                            let _ = self.player.play().await;
+                        }
+                    }
+                }
+            }
+            None => {
+                let _ = self.player.stop().await;
+            }
+        }
+        self.previewing.store(false, Ordering::SeqCst);
+    }
+
     pub async fn on_playback_ended(&self) -> Result<(), String> {
+        if self.previewing.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        if self.stop_after_current.swap(false, Ordering::SeqCst) {
+            return self.stop().await;
+        }
         let state = self.state.lock().await;
         if matches!(state.repeat_mode, RepeatMode::One) {
             if let Some(curr) = state.current_index {
@@ -338,80 +973,187 @@ impl QueueManager {
             }
         }
         drop(state);
-        self.next().await
+        if self.advance_past_failures().await? {
+            return Ok(());
+        }
+
+        match self.end_of_queue() {
+            EndOfQueueBehavior::Stop => Ok(()),
+            EndOfQueueBehavior::RepeatAll => {
+                let previous = self.state.lock().await.current_index.take();
+                if !self.advance_past_failures().await? {
+                    self.state.lock().await.current_index = previous;
+                }
+                Ok(())
+            }
+            EndOfQueueBehavior::PlaySimilar => self.play_similar().await,
+        }
+    }
+
+    /// Appends tracks similar to the current one and moves on to them.
+    async fn play_similar(&self) -> Result<(), String> {
+        let Some(track) = self.current_track().await else {
+            return Ok(());
+        };
+        let Some(provider) = track.provider_id.as_deref() else {
+            return Ok(());
+        };
+        let Some(provider) = self.get_provider(provider).await else {
+            return Ok(());
+        };
+        let similar = match provider.get_similar_tracks(&track.id, 20).await {
+            Ok(tracks) => tracks,
+            Err(e) => {
+                log::warn!("Could not fetch similar tracks for {}: {}", track.id, e);
+                return Ok(());
+            }
+        };
+
+        let hide_explicit = self.hides_explicit();
+        let queued: std::collections::HashSet<String> = {
+            let state = self.state.lock().await;
+            state.tracks.iter().map(|t| t.id.clone()).collect()
+        };
+        let fresh: Vec<Track> = similar
+            .into_iter()
+            .filter(|t| !queued.contains(&t.id) && !(hide_explicit && t.explicit))
+            .collect();
+        if fresh.is_empty() {
+            return Ok(());
+        }
+
+        self.add_tracks(fresh).await;
+        self.advance().await.map(|_| ())
     }
 
     pub async fn next(&self) -> Result<(), String> {
+        let skipped = !self.track_ended.load(Ordering::SeqCst)
+            && !self.is_stopped()
+            && !self.is_restoring()
+            && !self.previewing.load(Ordering::SeqCst);
+        if skipped {
+            if let Some(track) = self.current_track().await {
+                self.record_skip(&track).await;
+            }
+        }
+        self.advance().await.map(|_| ())
+    }
+
+    async fn record_skip(&self, track: &Track) {
         let mut state = self.state.lock().await;
+        for queued in state
+            .tracks
+            .iter_mut()
+            .filter(|t| t.id == track.id && t.provider_id == track.provider_id)
+        {
+            queued.skip_count += 1;
+        }
+        drop(state);
 
-        let next_idx = if state.shuffle {
-            if let Some(curr_raw) = state.current_index {
-                if let Some(pos_in_shuffle) =
-                    state.shuffled_indices.iter().position(|&r| r == curr_raw)
-                {
-                    if pos_in_shuffle + 1 < state.shuffled_indices.len() {
-                        Some(state.shuffled_indices[pos_in_shuffle + 1])
-                    } else if matches!(state.repeat_mode, RepeatMode::All) {
-                        Some(state.shuffled_indices[0])
-                    } else {
-                        None
-                    }
-                } else {
-                    state.shuffled_indices.first().cloned()
-                }
-            } else {
-                state.shuffled_indices.first().cloned()
-            }
-        } else if let Some(curr) = state.current_index {
-            if curr + 1 < state.tracks.len() {
-                Some(curr + 1)
-            } else if matches!(state.repeat_mode, RepeatMode::All) && !state.tracks.is_empty() {
-                Some(0)
-            } else {
-                None
-            }
-        } else if !state.tracks.is_empty() {
-            Some(0)
-        } else {
-            None
+        let Some(provider_id) = &track.provider_id else {
+            return;
         };
+        if let Some(provider) = self.get_provider(provider_id).await {
+            let _ = provider.record_skip(&track.id).await;
+        }
+    }
 
-        if let Some(idx) = next_idx {
+    /// Moves to the next playable track. Returns `false` when the queue has run out.
+    async fn advance(&self) -> Result<bool, String> {
+        let mut state = self.state.lock().await;
+
+        if let Some(idx) = next_playable_index(&state, self.hides_explicit()) {
             state.current_index = Some(idx);
             let track = state.tracks[idx].clone();
             drop(state);
             let res = self.load_track(&track, true).await;
-            let _ = self.save().await;
-            res
+            self.mark_dirty();
+            res.map(|_| true)
         } else {
-            Ok(())
+            Ok(false)
+        }
+    }
+
+    /// Like `advance`, but moves on past tracks that fail to load instead of
+    /// stopping on them, so one unplayable track doesn't end auto-advance.
+    async fn advance_past_failures(&self) -> Result<bool, String> {
+        let attempts = self.state.lock().await.tracks.len().max(1);
+        let mut last_error = None;
+        for _ in 0..attempts {
+            match self.advance().await {
+                Ok(advanced) => return Ok(advanced),
+                Err(e) => {
+                    log::warn!("Skipping track that failed to load: {}", e);
+                    last_error = Some(e);
+                }
+            }
         }
+        Err(last_error.unwrap_or_else(|| "No playable tracks in queue".to_string()))
     }
 
     pub async fn prev(&self) -> Result<(), String> {
         let mut state = self.state.lock().await;
-        if let Some(curr) = state.current_index {
-            if curr > 0 {
-                state.current_index = Some(curr - 1);
-                let track = state.tracks[curr - 1].clone();
-                drop(state);
-                let res = self.load_track(&track, true).await;
-                let _ = self.save().await;
-                return res;
-            }
+        if let Some(idx) = prev_index(&state) {
+            state.current_index = Some(idx);
+            let track = state.tracks[idx].clone();
+            drop(state);
+            let res = self.load_track(&track, true).await;
+            self.mark_dirty();
+            return res;
         }
         Ok(())
     }
 
+    /// Whether `next()` and `prev()` would move anywhere right now.
+    pub async fn navigation_state(&self) -> NavigationState {
+        let state = self.state.lock().await;
+        NavigationState {
+            has_next: next_playable_index(&state, self.hides_explicit()).is_some(),
+            has_prev: prev_index(&state).is_some(),
+        }
+    }
+
+    /// Receives a `TrackChanged` each time a queue track is loaded into the engine,
+    /// including replays of the same track. It is sent before the engine starts
+    /// loading, so it always precedes the engine's events for the new track.
+    /// Reloads of the current stream are not reported.
+    pub fn subscribe_track_changes(&self) -> broadcast::Receiver<TrackChanged> {
+        self.track_events.subscribe()
+    }
+
     async fn load_track(&self, track: &Track, auto_play: bool) -> Result<(), String> {
+        let _ = self.track_events.send(TrackChanged {
+            track: track.clone(),
+        });
+        self.load_stream(track, auto_play).await
+    }
+
+    async fn load_stream(&self, track: &Track, auto_play: bool) -> Result<(), String> {
+        self.previewing.store(false, Ordering::SeqCst);
+        self.track_ended.store(false, Ordering::SeqCst);
+        self.stopped.store(false, Ordering::SeqCst);
+        if auto_play {
+            self.end_restore();
+        }
+        let res = self.resolve_and_load(track, auto_play).await;
+        if let Err(e) = &res {
+            self.record_failure(track, e);
+        }
+        res
+    }
+
+    async fn resolve_and_load(&self, track: &Track, auto_play: bool) -> Result<(), String> {
+        if let Err(e) = self.player.set_track_gain(track.gain_db, track.peak).await {
+            log::warn!("Could not apply gain for {}: {}", track.id, e);
+        }
         let providers = self.providers.read().await;
 
+        // Ids can collide across providers, so a track that names its provider is
+        // never streamed from another one.
         if let Some(pid) = &track.provider_id {
-            if let Some(provider) = providers.get(pid) {
-                if let Ok(stream) = provider.resolve_stream(&track.id).await {
-                    return self.player.load(stream, auto_play).await;
-                }
-            }
+            let provider = providers.get(pid).ok_or("Provider not found".to_string())?;
+            let stream = provider.resolve_stream(&track.id).await?;
+            return self.player.load(stream, auto_play).await;
         }
 
         for (pid, provider) in providers.iter() {
@@ -444,6 +1186,31 @@ impl QueueManager {
         }
     }
 
+    /// Returns the next `count` tracks in the order `next()` would play them,
+    /// following the shuffle order, wrapping under `RepeatMode::All` and skipping
+    /// explicit tracks when they are hidden.
+    pub async fn peek_next(&self, count: usize) -> Vec<Track> {
+        let state = self.state.lock().await;
+        let hide_explicit = self.hides_explicit();
+
+        let mut upcoming = Vec::with_capacity(count);
+        let mut cursor = state.current_index;
+        let mut steps = 0;
+        while upcoming.len() < count && steps < count + state.tracks.len() {
+            let Some(idx) = next_index(&state, cursor) else {
+                break;
+            };
+            steps += 1;
+            cursor = Some(idx);
+            let track = &state.tracks[idx];
+            if hide_explicit && track.explicit {
+                continue;
+            }
+            upcoming.push(track.clone());
+        }
+        upcoming
+    }
+
     pub async fn current_track(&self) -> Option<Track> {
         let state = self.state.lock().await;
         if let Some(idx) = state.current_index {
@@ -454,9 +1221,482 @@ impl QueueManager {
     }
 }
 
-fn recalc_shuffle(state: &mut QueueState) {
+/// Shuffles the play order. With a `skip_threshold`, tracks skipped at least that
+/// many times are moved after all the others, still shuffled among themselves.
+fn recalc_shuffle(state: &mut QueueState, skip_threshold: Option<u32>) {
     let mut indices: Vec<usize> = (0..state.tracks.len()).collect();
     let mut rng = rand::rng();
     indices.shuffle(&mut rng);
+    if let Some(threshold) = skip_threshold.filter(|t| *t > 0) {
+        indices.sort_by_key(|&i| state.tracks[i].skip_count >= threshold);
+    }
     state.shuffled_indices = indices;
 }
+
+/// Drops already-played tracks, oldest in play order first, until the queue fits
+/// in `max_size`. The current track and everything after it are always kept, so
+/// the queue can stay above the limit.
+fn evict_played(state: &mut QueueState, max_size: Option<u32>) {
+    let Some(max_size) = max_size.filter(|m| *m > 0) else {
+        return;
+    };
+    let excess = state.tracks.len().saturating_sub(max_size as usize);
+    remove_played(state, excess);
+}
+
+/// Removes up to `count` already-played tracks, oldest in play order first, and
+/// remaps the current and shuffled indices to match.
+fn remove_played(state: &mut QueueState, count: usize) {
+    let Some(curr) = state.current_index else {
+        return;
+    };
+    if count == 0 {
+        return;
+    }
+
+    let played: Vec<usize> = if state.shuffle {
+        let pos = state
+            .shuffled_indices
+            .iter()
+            .position(|&i| i == curr)
+            .unwrap_or(0);
+        state.shuffled_indices[..pos].to_vec()
+    } else {
+        (0..curr).collect()
+    };
+    let evicted: std::collections::HashSet<usize> = played.into_iter().take(count).collect();
+    if evicted.is_empty() {
+        return;
+    }
+
+    let mut remap = Vec::with_capacity(state.tracks.len());
+    let mut kept = 0;
+    for i in 0..state.tracks.len() {
+        if evicted.contains(&i) {
+            remap.push(None);
+        } else {
+            remap.push(Some(kept));
+            kept += 1;
+        }
+    }
+    let mut i = 0;
+    state.tracks.retain(|_| {
+        let keep = remap[i].is_some();
+        i += 1;
+        keep
+    });
+    state.current_index = remap[curr];
+    state.shuffled_indices = state
+        .shuffled_indices
+        .iter()
+        .filter_map(|&i| remap[i])
+        .collect();
+}
+
+/// The index `next()` moves to: the next track in play order, skipping explicit
+/// ones when they're hidden.
+fn next_playable_index(state: &QueueState, hide_explicit: bool) -> Option<usize> {
+    let mut next_idx = next_index(state, state.current_index);
+    let mut skipped = 0;
+    while let Some(idx) = next_idx {
+        if !hide_explicit || !state.tracks[idx].explicit {
+            break;
+        }
+        skipped += 1;
+        next_idx = if skipped < state.tracks.len() {
+            next_index(state, Some(idx))
+        } else {
+            None
+        };
+    }
+    next_idx
+}
+
+/// The index `prev()` moves to. Always the previous queue position, regardless
+/// of shuffle or repeat.
+fn prev_index(state: &QueueState) -> Option<usize> {
+    state.current_index.and_then(|curr| curr.checked_sub(1))
+}
+
+fn next_index(state: &QueueState, from: Option<usize>) -> Option<usize> {
+    if state.shuffle {
+        if let Some(curr_raw) = from {
+            if let Some(pos_in_shuffle) = state.shuffled_indices.iter().position(|&r| r == curr_raw)
+            {
+                if pos_in_shuffle + 1 < state.shuffled_indices.len() {
+                    Some(state.shuffled_indices[pos_in_shuffle + 1])
+                } else if matches!(state.repeat_mode, RepeatMode::All) {
+                    Some(state.shuffled_indices[0])
+                } else {
+                    None
+                }
+            } else {
+                state.shuffled_indices.first().cloned()
+            }
+        } else {
+            state.shuffled_indices.first().cloned()
+        }
+    } else if let Some(curr) = from {
+        if curr + 1 < state.tracks.len() {
+            Some(curr + 1)
+        } else if matches!(state.repeat_mode, RepeatMode::All) && !state.tracks.is_empty() {
+            Some(0)
+        } else {
+            None
+        }
+    } else if !state.tracks.is_empty() {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::entities::{Genre, LibraryStats, SearchType, UnifiedSearchResult};
+    use crate::models::{Album, Artist, AudioDevice, EngineInfo};
+    use async_trait::async_trait;
+
+    struct MockEngine {
+        loaded: Arc<std::sync::Mutex<Vec<String>>>,
+        events: broadcast::Sender<PlayerEvent>,
+    }
+
+    impl MockEngine {
+        fn new() -> Self {
+            Self {
+                loaded: Arc::default(),
+                events: broadcast::channel(16).0,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AudioEngine for MockEngine {
+        async fn load(&self, stream: AudioStream, _auto_play: bool) -> Result<(), String> {
+            let url = match stream {
+                AudioStream::Url(url) | AudioStream::Segment { url, .. } => url,
+                AudioStream::Bytes(_) => "<bytes>".to_string(),
+            };
+            self.loaded.lock().unwrap().push(url);
+            Ok(())
+        }
+        async fn play(&self) -> Result<(), String> {
+            Ok(())
+        }
+        async fn pause(&self) -> Result<(), String> {
+            Ok(())
+        }
+        async fn stop(&self) -> Result<(), String> {
+            Ok(())
+        }
+        async fn seek(&self, _seconds: f64) -> Result<(), String> {
+            Ok(())
+        }
+        async fn set_volume(&self, _vol: f32) -> Result<(), String> {
+            Ok(())
+        }
+        async fn toggle_mute(&self, muted: Option<bool>) -> Result<bool, String> {
+            Ok(muted.unwrap_or(false))
+        }
+        async fn get_state(&self) -> PlayerState {
+            PlayerState::default()
+        }
+        async fn get_audio_devices(&self) -> Result<Vec<AudioDevice>, String> {
+            Ok(vec![])
+        }
+        async fn set_audio_device(&self, _device_id: Option<String>) -> Result<(), String> {
+            Ok(())
+        }
+        async fn toggle_exclusive_mode(&self, exclusive: Option<bool>) -> Result<bool, String> {
+            Ok(exclusive.unwrap_or(false))
+        }
+        async fn set_night_mode(&self, _enabled: bool) -> Result<(), String> {
+            Ok(())
+        }
+        async fn set_prevent_clipping(&self, _enabled: bool) -> Result<(), String> {
+            Ok(())
+        }
+        async fn set_track_gain(
+            &self,
+            _gain_db: Option<f32>,
+            _peak: Option<f32>,
+        ) -> Result<(), String> {
+            Ok(())
+        }
+        async fn get_engine_info(&self) -> Result<EngineInfo, String> {
+            Err("Not supported".to_string())
+        }
+        fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+            self.events.subscribe()
+        }
+    }
+
+    /// Serves the given track ids, streaming them as `<provider id>://<track id>`.
+    struct MockProvider {
+        id: String,
+        track_ids: Vec<String>,
+    }
+
+    #[async_trait]
+    impl LibraryProvider for MockProvider {
+        fn id(&self) -> &str {
+            &self.id
+        }
+        fn name(&self) -> &str {
+            &self.id
+        }
+        async fn get_recent_albums(&self, _limit: u32) -> Result<Vec<Album>, String> {
+            Ok(vec![])
+        }
+        async fn get_random_albums(&self, _limit: u32) -> Result<Vec<Album>, String> {
+            Ok(vec![])
+        }
+        async fn get_most_played_tracks(&self, _limit: u32) -> Result<Vec<Track>, String> {
+            Ok(vec![])
+        }
+        async fn get_library_stats(&self) -> Result<LibraryStats, String> {
+            Err("Not supported".to_string())
+        }
+        async fn get_genres(&self) -> Result<Vec<Genre>, String> {
+            Ok(vec![])
+        }
+        async fn get_favorites(&self) -> Result<Vec<Track>, String> {
+            Ok(vec![])
+        }
+        async fn search(
+            &self,
+            _query: &str,
+            _types: &[SearchType],
+        ) -> Result<UnifiedSearchResult, String> {
+            Ok(UnifiedSearchResult::default())
+        }
+        async fn get_artist(&self, _id: &str) -> Result<Artist, String> {
+            Err("Artist not found".to_string())
+        }
+        async fn get_album(&self, _id: &str) -> Result<Album, String> {
+            Err("Album not found".to_string())
+        }
+        async fn get_artist_albums(&self, _artist_id: &str) -> Result<Vec<Album>, String> {
+            Ok(vec![])
+        }
+        async fn get_album_tracks(&self, _album_id: &str) -> Result<Vec<Track>, String> {
+            Ok(vec![])
+        }
+        async fn get_track(&self, track_id: &str) -> Result<Track, String> {
+            if self.track_ids.iter().any(|id| id == track_id) {
+                Ok(track(track_id, Some(self.id.as_str())))
+            } else {
+                Err("Track not found".to_string())
+            }
+        }
+        async fn resolve_stream(&self, track_id: &str) -> Result<AudioStream, String> {
+            self.get_track(track_id).await?;
+            Ok(AudioStream::Url(format!("{}://{}", self.id, track_id)))
+        }
+    }
+
+    fn track(id: &str, provider_id: Option<&str>) -> Track {
+        Track {
+            id: id.to_string(),
+            provider_id: provider_id.map(str::to_string),
+            title: id.to_string(),
+            artist_id: String::new(),
+            artist_name: String::new(),
+            album_id: String::new(),
+            album_title: String::new(),
+            duration_sec: 180,
+            track_number: None,
+            disc_number: None,
+            year: None,
+            genre: None,
+            bitrate: None,
+            codec: None,
+            peak: None,
+            play_count: 0,
+            liked: false,
+            explicit: false,
+            rating: None,
+            skip_count: 0,
+            playlist_position: None,
+            gain_db: None,
+        }
+    }
+
+    fn queue_with(
+        engine: MockEngine,
+        providers: &[(&str, &[&str])],
+        state_file: &str,
+    ) -> Arc<QueueManager> {
+        let providers = providers
+            .iter()
+            .map(|(id, track_ids)| {
+                let provider: Arc<dyn LibraryProvider> = Arc::new(MockProvider {
+                    id: id.to_string(),
+                    track_ids: track_ids.iter().map(|t| t.to_string()).collect(),
+                });
+                (id.to_string(), provider)
+            })
+            .collect();
+        let state_path = std::env::temp_dir().join(format!(
+            "aether-queue-{}-{}.json",
+            std::process::id(),
+            state_file
+        ));
+        let _ = std::fs::remove_file(&state_path);
+        QueueManager::new(Box::new(engine), providers, state_path)
+    }
+
+    #[test]
+    fn trimming_under_shuffle_removes_tracks_played_in_shuffle_order() {
+        let mut state = QueueState {
+            tracks: ["a", "b", "c", "d", "e"]
+                .iter()
+                .map(|id| track(id, None))
+                .collect(),
+            current_index: Some(1),
+            shuffle: true,
+            shuffled_indices: vec![3, 0, 1, 4, 2],
+            ..Default::default()
+        };
+
+        remove_played(&mut state, usize::MAX);
+
+        let ids: Vec<&str> = state.tracks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["b", "c", "e"]);
+        assert_eq!(state.current_index, Some(0));
+        assert_eq!(state.shuffled_indices, [0, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn colliding_ids_stream_from_the_owning_provider() {
+        let engine = MockEngine::new();
+        let loaded = engine.loaded.clone();
+        let qm = queue_with(
+            engine,
+            &[("tidal", &["42"]), ("subsonic", &["42"])],
+            "colliding",
+        );
+
+        qm.resolve_and_load(&track("42", Some("tidal")), false)
+            .await
+            .unwrap();
+        qm.resolve_and_load(&track("42", Some("subsonic")), false)
+            .await
+            .unwrap();
+
+        assert_eq!(*loaded.lock().unwrap(), ["tidal://42", "subsonic://42"]);
+    }
+
+    #[tokio::test]
+    async fn failing_owner_does_not_fall_back_to_other_providers() {
+        let engine = MockEngine::new();
+        let loaded = engine.loaded.clone();
+        let qm = queue_with(engine, &[("tidal", &[]), ("subsonic", &["42"])], "owner");
+
+        let res = qm
+            .resolve_and_load(&track("42", Some("tidal")), false)
+            .await;
+
+        assert!(res.is_err());
+        assert!(loaded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn tracks_without_a_provider_are_searched_for() {
+        let engine = MockEngine::new();
+        let loaded = engine.loaded.clone();
+        let qm = queue_with(engine, &[("tidal", &[]), ("subsonic", &["42"])], "search");
+
+        qm.resolve_and_load(&track("42", None), false)
+            .await
+            .unwrap();
+
+        assert_eq!(*loaded.lock().unwrap(), ["subsonic://42"]);
+    }
+
+    #[tokio::test]
+    async fn restoring_a_session_is_flagged_until_playback_starts() {
+        let engine = MockEngine::new();
+        let loaded = engine.loaded.clone();
+        let qm = queue_with(engine, &[("local", &["1", "2"])], "restore");
+        let persisted = PersistedState {
+            queue: PersistedQueue {
+                tracks: vec!["1".to_string(), "2".to_string()],
+                track_providers: vec![Some("local".to_string()); 2],
+                current_index: Some(1),
+                ..Default::default()
+            },
+            player: PersistedPlayer {
+                volume: 0.5,
+                position: 30.0,
+            },
+        };
+        std::fs::write(&qm.state_path, serde_json::to_string(&persisted).unwrap()).unwrap();
+
+        qm.load_state(1.0).await;
+
+        assert!(qm.is_restoring());
+        assert_eq!(*loaded.lock().unwrap(), ["local://2"]);
+        assert_eq!(
+            qm.current_track().await.map(|t| t.id),
+            Some("2".to_string())
+        );
+
+        qm.play().await.unwrap();
+
+        assert!(!qm.is_restoring());
+        let _ = std::fs::remove_file(&qm.state_path);
+    }
+
+    #[tokio::test]
+    async fn previews_leave_a_restored_session_restoring() {
+        let engine = MockEngine::new();
+        let loaded = engine.loaded.clone();
+        let qm = queue_with(engine, &[("local", &["1"])], "preview");
+        let persisted = PersistedState {
+            queue: PersistedQueue {
+                tracks: vec!["1".to_string()],
+                track_providers: vec![Some("local".to_string())],
+                current_index: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        std::fs::write(&qm.state_path, serde_json::to_string(&persisted).unwrap()).unwrap();
+        qm.load_state(1.0).await;
+
+        qm.preview(AudioStream::Url("preview://1".to_string()), 0.0, 0.0)
+            .await
+            .unwrap();
+        assert!(qm.is_previewing());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(!qm.is_previewing());
+        assert!(qm.is_restoring());
+        assert_eq!(
+            *loaded.lock().unwrap(),
+            ["local://1", "preview://1", "local://1"]
+        );
+        let _ = std::fs::remove_file(&qm.state_path);
+    }
+
+    #[tokio::test]
+    async fn skips_count_against_the_owning_providers_track_only() {
+        let qm = queue_with(MockEngine::new(), &[], "skips");
+        qm.state.lock().await.tracks = vec![track("42", Some("tidal")), track("42", Some("local"))];
+
+        qm.record_skip(&track("42", Some("tidal"))).await;
+
+        let counts: Vec<u32> = qm
+            .state
+            .lock()
+            .await
+            .tracks
+            .iter()
+            .map(|t| t.skip_count)
+            .collect();
+        assert_eq!(counts, [1, 0]);
+    }
+}
@@ -1,21 +1,45 @@
+use crate::models::entities::Lyrics;
+use crate::players::switchable::SwitchableEngine;
 use crate::queue::QueueManager;
 use crate::util::discord::DiscordRpc;
+use crate::util::history::PlayHistory;
 use crate::util::lastfm::LastFmClient;
+use crate::util::lrclib::LrclibClient;
+use moka::future::Cache;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 pub struct AppState {
     pub queue: Arc<QueueManager>,
+    /// Handle to the engine the queue plays through, for swapping backends.
+    pub engine: SwitchableEngine,
     pub lastfm: Arc<Mutex<Option<LastFmClient>>>,
+    pub lrclib: LrclibClient,
     pub discord: Arc<Mutex<DiscordRpc>>,
+    pub history: Arc<PlayHistory>,
+    /// Cover palettes keyed by the hash of the image bytes.
+    pub palettes: Cache<String, Vec<String>>,
+    /// `get_lyrics` results keyed by `provider:track`, including misses.
+    pub lyrics: Cache<String, Option<Lyrics>>,
 }
 
 impl AppState {
-    pub fn new(queue: Arc<QueueManager>, lastfm: Option<LastFmClient>, discord: DiscordRpc) -> Self {
+    pub fn new(
+        queue: Arc<QueueManager>,
+        engine: SwitchableEngine,
+        lastfm: Option<LastFmClient>,
+        discord: DiscordRpc,
+        history: PlayHistory,
+    ) -> Self {
         Self {
             queue,
+            engine,
             lastfm: Arc::new(Mutex::new(lastfm)),
+            lrclib: LrclibClient::new(),
             discord: Arc::new(Mutex::new(discord)),
+            history: Arc::new(history),
+            palettes: Cache::new(512),
+            lyrics: Cache::new(256),
         }
     }
 }
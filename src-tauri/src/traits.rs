@@ -1,12 +1,29 @@
 use crate::models::{
-    entities::{Genre, LibraryStats, PlayerEvent, Playlist, UnifiedSearchResult},
-    Album, Artist, AudioDevice, PlayerState, Track,
+    entities::{
+        AdjacentAlbums, AlbumArtwork, ArtistFeature, ArtistMergeResult, ArtworkKind,
+        CoverCleanupResult, Genre, LibraryStats, Lyrics, LyricsSource, NowPlayingEntry,
+        PlayerEvent, Playlist, PodcastChannel, ProviderCapabilities, SearchType, TrackSort,
+        UnifiedSearchResult,
+    },
+    Album, Artist, AudioDevice, EngineInfo, PlayerState, Track,
 };
 use async_trait::async_trait;
+use std::collections::BTreeMap;
 use tokio::sync::broadcast;
 
+/// Returned by remote providers when a request exceeds its timeout, so the UI can
+/// offer a retry instead of showing a generic failure.
+pub const REQUEST_TIMEOUT_ERROR: &str = "Request timed out";
+
 pub enum AudioStream {
     Url(String),
+    /// Part of a file, e.g. one track of an album ripped to a single file with a
+    /// cue sheet. Engines report positions relative to `start`.
+    Segment {
+        url: String,
+        start: f64,
+        end: Option<f64>,
+    },
     #[allow(dead_code)]
     Bytes(Vec<u8>),
 }
@@ -14,8 +31,15 @@ pub enum AudioStream {
 #[async_trait]
 pub trait LibraryProvider: Send + Sync {
     fn id(&self) -> &str;
-    #[allow(dead_code)]
+    /// Label for the source, as configured by the user.
     fn name(&self) -> &str;
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::default()
+    }
+    /// Cheap check that the source can serve requests right now.
+    async fn is_online(&self) -> bool {
+        true
+    }
 
     async fn get_recent_albums(&self, limit: u32) -> Result<Vec<Album>, String>;
     async fn get_random_albums(&self, limit: u32) -> Result<Vec<Album>, String>;
@@ -27,26 +51,187 @@ pub trait LibraryProvider: Send + Sync {
     }
     async fn get_favorites(&self) -> Result<Vec<Track>, String>;
 
-    async fn search(&self, query: &str) -> Result<UnifiedSearchResult, String>;
+    async fn search(
+        &self,
+        query: &str,
+        types: &[SearchType],
+    ) -> Result<UnifiedSearchResult, String>;
 
     async fn get_artist(&self, id: &str) -> Result<Artist, String>;
     async fn get_album(&self, id: &str) -> Result<Album, String>;
     async fn get_artist_albums(&self, artist_id: &str) -> Result<Vec<Album>, String>;
+    async fn get_artist_related(&self, _artist_id: &str) -> Result<Vec<Artist>, String> {
+        Err("Not supported".to_string())
+    }
+    async fn get_artist_features(&self, _artist_id: &str) -> Result<Vec<ArtistFeature>, String> {
+        Ok(vec![])
+    }
+    async fn get_artist_tracks(
+        &self,
+        artist_id: &str,
+        sort: TrackSort,
+        limit: Option<u32>,
+    ) -> Result<Vec<Track>, String> {
+        let albums = self.get_artist_albums(artist_id).await?;
+        let results =
+            futures::future::join_all(albums.iter().map(|a| self.get_album_tracks(&a.id))).await;
+        let mut tracks: Vec<Track> = results
+            .into_iter()
+            .filter_map(|r| r.ok())
+            .flatten()
+            .collect();
+        sort.sort(&mut tracks);
+        if let Some(limit) = limit {
+            tracks.truncate(limit as usize);
+        }
+        Ok(tracks)
+    }
+    /// Previous and next album by release year among the artist's albums.
+    /// Albums without a year sort after dated ones, by title.
+    async fn get_adjacent_albums(
+        &self,
+        artist_id: &str,
+        album_id: &str,
+    ) -> Result<AdjacentAlbums, String> {
+        let mut albums = self.get_artist_albums(artist_id).await?;
+        albums.sort_by_cached_key(|a| (a.year.is_none(), a.year, a.title.to_lowercase()));
+        let Some(idx) = albums.iter().position(|a| a.id == album_id) else {
+            return Err("Album not found for artist".to_string());
+        };
+        let next = albums.get(idx + 1).cloned();
+        let prev = idx.checked_sub(1).map(|i| albums[i].clone());
+        Ok(AdjacentAlbums { prev, next })
+    }
+    async fn merge_artists(
+        &self,
+        _keep_id: &str,
+        _merge_id: &str,
+    ) -> Result<ArtistMergeResult, String> {
+        Err("Not supported".to_string())
+    }
+    async fn get_album_artwork(&self, album_id: &str) -> Result<Vec<AlbumArtwork>, String> {
+        let album = self.get_album(album_id).await?;
+        Ok(album
+            .cover_art
+            .map(|url| AlbumArtwork {
+                kind: ArtworkKind::Front,
+                url,
+            })
+            .into_iter()
+            .collect())
+    }
+    /// Raw bytes of the album's cover image.
+    async fn get_cover_image(&self, _album_id: &str) -> Result<Vec<u8>, String> {
+        Err("Not supported".to_string())
+    }
+    /// Removes cached cover files nothing references anymore and repairs albums
+    /// pointing at files that have gone missing.
+    async fn cleanup_covers(&self) -> Result<CoverCleanupResult, String> {
+        Err("Not supported".to_string())
+    }
+    async fn merge_albums(&self, _keep_id: &str, _merge_id: &str) -> Result<u32, String> {
+        Err("Not supported".to_string())
+    }
+    async fn split_album(
+        &self,
+        _album_id: &str,
+        _track_ids: &[String],
+        _new_title: &str,
+    ) -> Result<Album, String> {
+        Err("Not supported".to_string())
+    }
     async fn get_album_tracks(&self, album_id: &str) -> Result<Vec<Track>, String>;
+    /// Warms whatever cache backs `get_album` / `get_album_tracks` so opening the
+    /// album right after is instant.
+    async fn prefetch_album(&self, album_id: &str) -> Result<(), String> {
+        self.get_album_tracks(album_id).await.map(|_| ())
+    }
+    async fn get_similar_tracks(&self, _track_id: &str, _count: u32) -> Result<Vec<Track>, String> {
+        Err("Not supported".to_string())
+    }
     async fn get_track(&self, track_id: &str) -> Result<Track, String>;
+    /// Like `get_track`, but skips any cached response and replaces it.
+    async fn refresh_track(&self, track_id: &str) -> Result<Track, String> {
+        self.get_track(track_id).await
+    }
+    async fn get_tracks(&self, track_ids: &[String]) -> Result<Vec<Track>, String> {
+        let results =
+            futures::future::join_all(track_ids.iter().map(|id| self.get_track(id))).await;
+        Ok(results.into_iter().filter_map(|r| r.ok()).collect())
+    }
     async fn set_track_liked(&self, _track_id: &str, _liked: bool) -> Result<(), String> {
         Err("Not supported".to_string())
     }
+    async fn set_track_explicit(&self, _track_id: &str, _explicit: bool) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
+    async fn set_track_rating(&self, _track_id: &str, _rating: u8) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
+    /// Stores a manual gain for the track, or clears it with `None`.
+    async fn set_track_gain(&self, _track_id: &str, _gain_db: Option<f32>) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
+    /// Counts a manual skip of the track.
+    async fn record_skip(&self, _track_id: &str) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
+    async fn get_tracks_by_rating(&self, _min_rating: u8) -> Result<Vec<Track>, String> {
+        Ok(vec![])
+    }
+    async fn get_tracks_by_format(
+        &self,
+        _codec: Option<&str>,
+        _min_bitrate: Option<u32>,
+    ) -> Result<Vec<Track>, String> {
+        Ok(vec![])
+    }
+    async fn get_lossless_tracks(&self) -> Result<Vec<Track>, String> {
+        Ok(vec![])
+    }
+    /// Lyrics from one `source`, or `None` when the provider has none there or
+    /// can't read that source at all.
+    async fn get_lyrics(
+        &self,
+        _track_id: &str,
+        _source: LyricsSource,
+    ) -> Result<Option<Lyrics>, String> {
+        Ok(None)
+    }
+    /// Every tag in the track's file, keyed by the format's own field name
+    /// (e.g. `REPLAYGAIN_TRACK_GAIN`), including ones `Track` doesn't carry.
+    async fn get_audio_tags(
+        &self,
+        _track_id: &str,
+    ) -> Result<BTreeMap<String, Vec<String>>, String> {
+        Err("Not supported".to_string())
+    }
+    async fn is_favorite(&self, track_id: &str) -> Result<bool, String> {
+        self.get_track(track_id).await.map(|t| t.liked)
+    }
 
     async fn get_playlists(&self) -> Result<Vec<Playlist>, String> {
         Ok(vec![])
     }
+    async fn get_playlist(&self, id: &str) -> Result<Playlist, String> {
+        self.get_playlists()
+            .await?
+            .into_iter()
+            .find(|p| p.id == id)
+            .ok_or("Playlist not found".to_string())
+    }
     async fn create_playlist(&self, _name: &str) -> Result<Playlist, String> {
         Err("Not supported".to_string())
     }
     async fn delete_playlist(&self, _id: &str) -> Result<(), String> {
         Err("Not supported".to_string())
     }
+    async fn rename_playlist(&self, _id: &str, _name: &str) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
+    async fn set_playlist_cover(&self, _id: &str, _image_path: &str) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
     async fn get_playlist_tracks(&self, _id: &str) -> Result<Vec<Track>, String> {
         Ok(vec![])
     }
@@ -60,6 +245,22 @@ pub trait LibraryProvider: Send + Sync {
     ) -> Result<(), String> {
         Err("Not supported".to_string())
     }
+    /// Removes the entry at `index` in `get_playlist_tracks` order, so only that
+    /// occurrence of a track listed more than once goes away.
+    async fn remove_from_playlist_at(&self, _playlist_id: &str, _index: u32) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
+
+    async fn get_podcasts(&self) -> Result<Vec<PodcastChannel>, String> {
+        Err("Not supported".to_string())
+    }
+    async fn get_podcast_episodes(&self, _channel_id: &str) -> Result<Vec<Track>, String> {
+        Err("Not supported".to_string())
+    }
+
+    async fn get_server_now_playing(&self) -> Result<Vec<NowPlayingEntry>, String> {
+        Err("Not supported".to_string())
+    }
 
     async fn resolve_stream(&self, track_id: &str) -> Result<AudioStream, String>;
 
@@ -67,6 +268,17 @@ pub trait LibraryProvider: Send + Sync {
         Ok(())
     }
 
+    /// Stops a running `scan` as soon as possible, keeping what it already
+    /// committed.
+    async fn cancel_scan(&self) -> Result<(), String> {
+        Err("Not supported".to_string())
+    }
+
+    /// Drops tracks under a directory that no longer exists, without a rescan.
+    async fn remove_tracks_under_path(&self, _path_prefix: &str) -> Result<u32, String> {
+        Err("Not supported".to_string())
+    }
+
     async fn add_root(&self, _path: &str) -> Result<(), String> {
         Err("Not supported".to_string())
     }
@@ -80,6 +292,7 @@ pub trait AudioEngine: Send + Sync {
     async fn stop(&self) -> Result<(), String>;
     async fn seek(&self, seconds: f64) -> Result<(), String>;
     async fn set_volume(&self, vol: f32) -> Result<(), String>;
+    async fn toggle_mute(&self, muted: Option<bool>) -> Result<bool, String>;
 
     async fn get_state(&self) -> PlayerState;
 
@@ -88,5 +301,15 @@ pub trait AudioEngine: Send + Sync {
 
     async fn toggle_exclusive_mode(&self, exclusive: Option<bool>) -> Result<bool, String>;
 
+    async fn set_night_mode(&self, enabled: bool) -> Result<(), String>;
+
+    async fn set_prevent_clipping(&self, enabled: bool) -> Result<(), String>;
+
+    /// Extra gain for the loaded track in dB, separate from the volume setting.
+    /// `peak` is the track's ReplayGain peak, used to keep the gain from clipping.
+    async fn set_track_gain(&self, gain_db: Option<f32>, peak: Option<f32>) -> Result<(), String>;
+
+    async fn get_engine_info(&self) -> Result<EngineInfo, String>;
+
     fn subscribe(&self) -> broadcast::Receiver<PlayerEvent>;
 }
@@ -201,9 +201,18 @@ pub fn start_discord_rpc_service(
         while let Ok(event) = rx.recv().await {
             match event {
                 PlayerEvent::Playing | PlayerEvent::Paused => {}
+                PlayerEvent::Stopped => {
+                    last_track_id = None;
+                    discord.lock().await.clear();
+                    continue;
+                }
                 _ => continue,
             }
 
+            if queue.is_restoring() || queue.is_previewing() {
+                continue;
+            }
+
             if let Some(track) = queue.current_track().await {
                 let track_changed = last_track_id.as_deref() != Some(&track.id);
 
@@ -240,7 +249,7 @@ pub fn start_discord_rpc_service(
                             }
                         }
 
-                        if let Ok(info) = client.get_artist_info(&track.artist_name).await {
+                        if let Ok(info) = client.get_artist_info(&track.artist_name, None).await {
                             if let Some(images) = info.image {
                                 cached_artist_image = images.last().map(|i| i.url.clone());
                             }
@@ -0,0 +1,262 @@
+use crate::models::entities::{DailyPlays, ListeningReport, PlayerEvent, ReportEntry, Track};
+use crate::queue::QueueManager;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use std::path::Path;
+use std::sync::Arc;
+
+pub struct PlayHistory {
+    db: SqlitePool,
+}
+
+impl PlayHistory {
+    pub fn new(db_path: &Path) -> Self {
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true);
+        Self {
+            db: SqlitePoolOptions::new().connect_lazy_with(options),
+        }
+    }
+
+    pub async fn init(&self) -> Result<()> {
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS play_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                track_id TEXT NOT NULL,
+                provider_id TEXT,
+                album_id TEXT,
+                played_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_play_history_track ON play_history(track_id);
+            CREATE INDEX IF NOT EXISTS idx_play_history_album ON play_history(album_id);
+            CREATE INDEX IF NOT EXISTS idx_play_history_played_at ON play_history(played_at);
+            "#,
+        )
+        .execute(&self.db)
+        .await?;
+
+        // Denormalized so reports work for remote tracks that aren't in any local table.
+        let columns: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM pragma_table_info('play_history')")
+                .fetch_all(&self.db)
+                .await?;
+        for (column, definition) in [
+            ("title", "TEXT"),
+            ("artist_name", "TEXT"),
+            ("album_title", "TEXT"),
+            ("duration_sec", "INTEGER"),
+        ] {
+            if !columns.iter().any(|c| c == column) {
+                sqlx::query(&format!(
+                    "ALTER TABLE play_history ADD COLUMN {} {}",
+                    column, definition
+                ))
+                .execute(&self.db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn record(&self, track: &Track) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO play_history
+            (track_id, provider_id, album_id, title, artist_name, album_title, duration_sec)
+            VALUES (?, ?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(&track.id)
+        .bind(&track.provider_id)
+        .bind(&track.album_id)
+        .bind(&track.title)
+        .bind(&track.artist_name)
+        .bind(&track.album_title)
+        .bind(track.duration_sec)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Aggregates plays in `[from, to)`. Plays recorded before titles were
+    /// stored in history count towards totals but not the top lists.
+    pub async fn report(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<ListeningReport> {
+        const RANGE: &str = "played_at >= datetime(?) AND played_at < datetime(?)";
+
+        let totals = sqlx::query(&format!(
+            "SELECT COUNT(*) as plays, COALESCE(SUM(duration_sec), 0) as secs FROM play_history WHERE {}",
+            RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.db)
+        .await?;
+
+        let top_artists = sqlx::query(&format!(
+            r#"SELECT artist_name, COUNT(*) as plays FROM play_history
+            WHERE {} AND artist_name IS NOT NULL
+            GROUP BY artist_name COLLATE NOCASE
+            ORDER BY plays DESC LIMIT ?"#,
+            RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|row| ReportEntry {
+            id: None,
+            provider_id: None,
+            name: row.get("artist_name"),
+            artist_name: None,
+            plays: row.get::<i64, _>("plays") as u32,
+        })
+        .collect();
+
+        let top_albums = sqlx::query(&format!(
+            r#"SELECT provider_id, album_id, MAX(album_title) as name,
+                MAX(artist_name) as artist_name, COUNT(*) as plays
+            FROM play_history
+            WHERE {} AND album_id IS NOT NULL AND album_id != '' AND album_title IS NOT NULL
+            GROUP BY provider_id, album_id
+            ORDER BY plays DESC LIMIT ?"#,
+            RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|row| ReportEntry {
+            id: row.get("album_id"),
+            provider_id: row.get("provider_id"),
+            name: row.get("name"),
+            artist_name: row.get("artist_name"),
+            plays: row.get::<i64, _>("plays") as u32,
+        })
+        .collect();
+
+        let top_tracks = sqlx::query(&format!(
+            r#"SELECT provider_id, track_id, MAX(title) as name,
+                MAX(artist_name) as artist_name, COUNT(*) as plays
+            FROM play_history
+            WHERE {} AND title IS NOT NULL
+            GROUP BY provider_id, track_id
+            ORDER BY plays DESC LIMIT ?"#,
+            RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|row| ReportEntry {
+            id: row.get("track_id"),
+            provider_id: row.get("provider_id"),
+            name: row.get("name"),
+            artist_name: row.get("artist_name"),
+            plays: row.get::<i64, _>("plays") as u32,
+        })
+        .collect();
+
+        let daily = sqlx::query(&format!(
+            r#"SELECT date(played_at, 'localtime') as day, COUNT(*) as plays
+            FROM play_history WHERE {}
+            GROUP BY day ORDER BY day"#,
+            RANGE
+        ))
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|row| DailyPlays {
+            date: row.get("day"),
+            plays: row.get::<i64, _>("plays") as u32,
+        })
+        .collect();
+
+        Ok(ListeningReport {
+            total_plays: totals.get::<i64, _>("plays") as u32,
+            total_listening_secs: totals.get::<i64, _>("secs") as u64,
+            top_artists,
+            top_albums,
+            top_tracks,
+            daily,
+        })
+    }
+
+    /// Most recently played `(provider_id, track_id)` pairs, newest first.
+    pub async fn recent_tracks(&self, limit: u32) -> Result<Vec<(Option<String>, String)>> {
+        let rows = sqlx::query(
+            r#"SELECT provider_id, track_id FROM play_history
+            GROUP BY provider_id, track_id
+            ORDER BY MAX(id) DESC
+            LIMIT ?"#,
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("provider_id"), row.get("track_id")))
+            .collect())
+    }
+
+    pub async fn recent_albums(&self, limit: u32) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query(
+            r#"SELECT provider_id, album_id FROM play_history
+            WHERE provider_id IS NOT NULL AND album_id IS NOT NULL AND album_id != ''
+            GROUP BY provider_id, album_id
+            ORDER BY MAX(id) DESC
+            LIMIT ?"#,
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("provider_id"), row.get("album_id")))
+            .collect())
+    }
+}
+
+pub fn start_history_service(queue: Arc<QueueManager>, history: Arc<PlayHistory>) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = history.init().await {
+            log::error!("Failed to initialize play history: {}", e);
+            return;
+        }
+
+        let mut rx = queue.player.subscribe();
+        let mut current_track_id: Option<String> = None;
+
+        while let Ok(event) = rx.recv().await {
+            if queue.is_restoring() || queue.is_previewing() {
+                continue;
+            }
+
+            if let PlayerEvent::Playing = event {
+                if let Some(track) = queue.current_track().await {
+                    if current_track_id.as_deref() != Some(&track.id) {
+                        current_track_id = Some(track.id.clone());
+                        if let Err(e) = history.record(&track).await {
+                            log::warn!("Failed to record play history: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
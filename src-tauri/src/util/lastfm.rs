@@ -1,98 +1,205 @@
-use crate::models::entities::PlayerEvent;
+use crate::models::entities::{PlayerEvent, Track};
+use crate::models::AppConfig;
 use crate::queue::QueueManager;
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Mutex};
 
 const API_ROOT: &str = "https://ws.audioscrobbler.com/2.0/";
 const API_KEY: &str = env!("LASTFM_API_KEY");
 const API_SECRET: &str = env!("LASTFM_API_SECRET");
 
+/// Listening state for the track the scrobbler is following. Time only counts
+/// while the engine reports playing, so pauses and stalls before a resume don't
+/// push a track over the scrobble threshold.
+struct ScrobbleSession {
+    track: Track,
+    /// When playback of the track first started; submitted as the scrobble time.
+    started_at: Option<i64>,
+    listened: Duration,
+    playing_since: Option<Instant>,
+    now_playing_sent: bool,
+    scrobbled: bool,
+}
+
+impl ScrobbleSession {
+    fn new(track: Track) -> Self {
+        Self {
+            track,
+            started_at: None,
+            listened: Duration::ZERO,
+            playing_since: None,
+            now_playing_sent: false,
+            scrobbled: false,
+        }
+    }
+
+    fn resume(&mut self) {
+        self.started_at
+            .get_or_insert_with(|| chrono::Utc::now().timestamp());
+        self.playing_since.get_or_insert_with(Instant::now);
+    }
+
+    fn pause(&mut self) {
+        if let Some(since) = self.playing_since.take() {
+            self.listened += since.elapsed();
+        }
+    }
+
+    fn listened(&self) -> Duration {
+        self.listened + self.playing_since.map(|s| s.elapsed()).unwrap_or_default()
+    }
+
+    /// Half the track or four minutes, whichever comes first. Streams with an
+    /// unknown length report 0 and tracks of 30 seconds or less are never scrobbled.
+    fn threshold(&self) -> Option<Duration> {
+        let duration = self.track.duration_sec as f64;
+        (duration > 30.0).then(|| Duration::from_secs_f64((duration / 2.0).min(240.0)))
+    }
+}
+
+/// How often listened time is checked against the threshold, independent of
+/// how often the engine sends time updates.
+const SCROBBLE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 pub fn start_scrobbling_service(
     queue: Arc<QueueManager>,
     lastfm: Arc<Mutex<Option<LastFmClient>>>,
 ) {
     tauri::async_runtime::spawn(async move {
         let mut rx = queue.player.subscribe();
-        let mut current_track_id: Option<String> = None;
-        let mut scrobbled = false;
-
-        while let Ok(event) = rx.recv().await {
-            let client = {
-                let guard = lastfm.lock().await;
-                guard.clone()
-            };
-
-            if let Some(client) = client {
-                match &event {
-                    PlayerEvent::Playing => {
-                        if let Some(track) = queue.current_track().await {
-                            if current_track_id.as_deref() != Some(&track.id) {
-                                current_track_id = Some(track.id.clone());
-                                scrobbled = false;
-
-                                let client = client.clone();
-                                let artist = track.artist_name.clone();
-                                let title = track.title.clone();
-                                let album = track.album_title.clone();
-
-                                tauri::async_runtime::spawn(async move {
-                                    if let Err(e) = client
-                                        .update_now_playing(&artist, &title, Some(&album))
-                                        .await
-                                    {
-                                        log::warn!("Last.fm Now Playing error: {}", e);
-                                    }
-                                });
-                            }
+        let mut track_rx = queue.subscribe_track_changes();
+        let mut tick = tokio::time::interval(SCROBBLE_CHECK_INTERVAL);
+        let mut session: Option<ScrobbleSession> = None;
+
+        loop {
+            tokio::select! {
+                // A track change must be handled before the new track's `Playing`,
+                // which may already be waiting on the engine channel.
+                biased;
+                changed = track_rx.recv() => match changed {
+                    Ok(changed) => {
+                        if let Some(session) = &mut session {
+                            session.pause();
+                            submit_scrobble(&lastfm, session).await;
                         }
+                        if queue.is_restoring() || queue.is_previewing() {
+                            session = None;
+                            continue;
+                        }
+                        session = Some(ScrobbleSession::new(changed.track));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                event = rx.recv() => {
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    if queue.is_restoring() || queue.is_previewing() {
+                        continue;
                     }
-                    PlayerEvent::TimeUpdate(pos) => {
-                        if !scrobbled {
-                            if let Some(track) = queue.current_track().await {
-                                if current_track_id.as_deref() == Some(&track.id) {
-                                    let duration = track.duration_sec as f64;
-                                    if duration > 30.0 {
-                                        let threshold = (duration / 2.0).min(240.0);
-                                        if *pos >= threshold {
-                                            scrobbled = true;
-                                            let client = client.clone();
-                                            let artist = track.artist_name.clone();
-                                            let title = track.title.clone();
-                                            let album = track.album_title.clone();
-                                            let timestamp = chrono::Utc::now().timestamp();
-
-                                            tauri::async_runtime::spawn(async move {
-                                                if let Err(e) = client
-                                                    .scrobble(
-                                                        &artist,
-                                                        &title,
-                                                        timestamp,
-                                                        Some(&album),
-                                                    )
-                                                    .await
-                                                {
-                                                    log::error!("Last.fm Scrobble error: {}", e);
-                                                } else {
-                                                    log::info!("Scrobbled: {} - {}", artist, title);
-                                                }
-                                            });
-                                        }
-                                    }
+                    match event {
+                        PlayerEvent::Playing => {
+                            // A track restored at startup never produced a TrackChanged
+                            // we acted on, so pick it up when the user starts playback.
+                            if session.is_none() {
+                                session = queue.current_track().await.map(ScrobbleSession::new);
+                            }
+                            if let Some(session) = &mut session {
+                                session.resume();
+                                if !session.now_playing_sent {
+                                    session.now_playing_sent = true;
+                                    send_now_playing(&lastfm, &session.track).await;
                                 }
                             }
                         }
+                        PlayerEvent::Paused
+                        | PlayerEvent::Stopped
+                        | PlayerEvent::Ended
+                        | PlayerEvent::Error(_) => {
+                            if let Some(session) = &mut session {
+                                session.pause();
+                                submit_scrobble(&lastfm, session).await;
+                            }
+                        }
+                        PlayerEvent::TimeUpdate(_) => {
+                            if let Some(session) = &mut session {
+                                submit_scrobble(&lastfm, session).await;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ = tick.tick() => {
+                    if let Some(session) = &mut session {
+                        submit_scrobble(&lastfm, session).await;
                     }
-                    _ => {}
                 }
             }
         }
     });
 }
 
+async fn send_now_playing(lastfm: &Mutex<Option<LastFmClient>>, track: &Track) {
+    let Some(client) = lastfm.lock().await.clone() else {
+        return;
+    };
+    let artist = track.artist_name.clone();
+    let title = track.title.clone();
+    let album = track.album_title.clone();
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = client
+            .update_now_playing(&artist, &title, Some(&album))
+            .await
+        {
+            log::warn!("Last.fm Now Playing error: {}", e);
+        }
+    });
+}
+
+/// Scrobbles the session's track once its listened time reaches the threshold.
+async fn submit_scrobble(lastfm: &Mutex<Option<LastFmClient>>, session: &mut ScrobbleSession) {
+    if session.scrobbled {
+        return;
+    }
+    let Some(threshold) = session.threshold() else {
+        return;
+    };
+    if session.listened() < threshold {
+        return;
+    }
+    session.scrobbled = true;
+
+    let Some(client) = lastfm.lock().await.clone() else {
+        return;
+    };
+    let artist = session.track.artist_name.clone();
+    let title = session.track.title.clone();
+    let album = session.track.album_title.clone();
+    let timestamp = session
+        .started_at
+        .unwrap_or_else(|| chrono::Utc::now().timestamp());
+
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = client
+            .scrobble(&artist, &title, timestamp, Some(&album))
+            .await
+        {
+            log::error!("Last.fm Scrobble error: {}", e);
+        } else {
+            log::info!("Scrobbled: {} - {}", artist, title);
+        }
+    });
+}
+
 #[derive(Clone)]
 pub struct LastFmClient {
     username: Option<String>,
@@ -159,6 +266,22 @@ struct ArtistInfoResponse {
     artist: ArtistInfo,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AlbumInfo {
+    pub name: String,
+    pub artist: String,
+    pub mbid: Option<String>,
+    pub url: String,
+    pub image: Option<Vec<Image>>,
+    pub releasedate: Option<String>,
+    pub wiki: Option<Bio>,
+}
+
+#[derive(Deserialize)]
+struct AlbumInfoResponse {
+    album: AlbumInfo,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TrackArtist {
     pub name: String,
@@ -196,6 +319,30 @@ struct TrackInfoResponse {
     track: TrackInfo,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SimilarTrackArtist {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SimilarTrack {
+    pub name: String,
+    pub artist: SimilarTrackArtist,
+    #[serde(rename = "match")]
+    pub match_score: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct SimilarTracks {
+    #[serde(default)]
+    track: Vec<SimilarTrack>,
+}
+
+#[derive(Deserialize)]
+struct SimilarTracksResponse {
+    similartracks: SimilarTracks,
+}
+
 #[derive(Deserialize)]
 pub struct LastFmSession {
     pub name: String,
@@ -221,6 +368,21 @@ impl LastFmClient {
         }
     }
 
+    /// Client for the saved session, or `None` when Last.fm is turned off or
+    /// nobody is logged in.
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        config
+            .lastfm_session
+            .as_ref()
+            .filter(|session| session.enabled)
+            .map(|session| {
+                Self::new(
+                    Some(session.username.clone()),
+                    Some(session.session_key.clone()),
+                )
+            })
+    }
+
     pub fn set_session_key(&mut self, session_key: String) {
         self.session_key = Some(session_key);
     }
@@ -313,10 +475,13 @@ impl LastFmClient {
         Ok(data.session)
     }
 
-    pub async fn get_artist_info(&self, artist: &str) -> Result<ArtistInfo> {
+    pub async fn get_artist_info(&self, artist: &str, mbid: Option<&str>) -> Result<ArtistInfo> {
         let mut params = HashMap::new();
         params.insert("method".to_string(), "artist.getInfo".to_string());
-        params.insert("artist".to_string(), artist.to_string());
+        match mbid {
+            Some(mbid) => params.insert("mbid".to_string(), mbid.to_string()),
+            None => params.insert("artist".to_string(), artist.to_string()),
+        };
         params.insert("api_key".to_string(), API_KEY.to_string());
         params.insert("format".to_string(), "json".to_string());
         params.insert("autocorrect".to_string(), "1".to_string());
@@ -346,6 +511,36 @@ impl LastFmClient {
         Ok(data.artist)
     }
 
+    pub async fn get_album_info(&self, artist: &str, album: &str) -> Result<AlbumInfo> {
+        let mut params = HashMap::new();
+        params.insert("method".to_string(), "album.getInfo".to_string());
+        params.insert("artist".to_string(), artist.to_string());
+        params.insert("album".to_string(), album.to_string());
+        params.insert("api_key".to_string(), API_KEY.to_string());
+        params.insert("format".to_string(), "json".to_string());
+        params.insert("autocorrect".to_string(), "1".to_string());
+
+        let res = self
+            .client
+            .get(API_ROOT)
+            .query(&params)
+            .send()
+            .await
+            .context("Failed to send Last.fm request")?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Last.fm API Error {}: {}", status, text));
+        }
+
+        let data: AlbumInfoResponse = res
+            .json()
+            .await
+            .context("Failed to parse Last.fm response")?;
+        Ok(data.album)
+    }
+
     pub async fn get_track_info(&self, artist: &str, track: &str) -> Result<TrackInfo> {
         let mut params = HashMap::new();
         params.insert("method".to_string(), "track.getInfo".to_string());
@@ -380,6 +575,43 @@ impl LastFmClient {
         Ok(data.track)
     }
 
+    /// Tracks Last.fm considers similar to the given one, best match first.
+    pub async fn get_similar_tracks(
+        &self,
+        artist: &str,
+        track: &str,
+        limit: u32,
+    ) -> Result<Vec<SimilarTrack>> {
+        let mut params = HashMap::new();
+        params.insert("method".to_string(), "track.getSimilar".to_string());
+        params.insert("artist".to_string(), artist.to_string());
+        params.insert("track".to_string(), track.to_string());
+        params.insert("limit".to_string(), limit.to_string());
+        params.insert("api_key".to_string(), API_KEY.to_string());
+        params.insert("format".to_string(), "json".to_string());
+        params.insert("autocorrect".to_string(), "1".to_string());
+
+        let res = self
+            .client
+            .get(API_ROOT)
+            .query(&params)
+            .send()
+            .await
+            .context("Failed to send Last.fm request")?;
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Last.fm API Error {}: {}", status, text));
+        }
+
+        let data: SimilarTracksResponse = res
+            .json()
+            .await
+            .context("Failed to parse Last.fm response")?;
+        Ok(data.similartracks.track)
+    }
+
     pub async fn scrobble(
         &self,
         artist: &str,
@@ -0,0 +1,70 @@
+use crate::models::entities::{Lyrics, LyricsSource};
+use anyhow::Result;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+const API_ROOT: &str = "https://lrclib.net/api";
+/// lrclib asks clients to identify themselves.
+const USER_AGENT: &str = concat!(
+    "Aether Player/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/vMohammad24/aether-player)"
+);
+
+#[derive(Clone)]
+pub struct LrclibClient {
+    client: Client,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibTrack {
+    plain_lyrics: Option<String>,
+    synced_lyrics: Option<String>,
+}
+
+impl LrclibClient {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder()
+                .user_agent(USER_AGENT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Looks up lyrics by exact signature. lrclib matches the duration within a
+    /// couple of seconds, so slightly different rips of the same track still hit.
+    pub async fn get(
+        &self,
+        artist: &str,
+        title: &str,
+        album: &str,
+        duration_sec: u32,
+    ) -> Result<Option<Lyrics>> {
+        let duration = duration_sec.to_string();
+        let res = self
+            .client
+            .get(format!("{}/get", API_ROOT))
+            .query(&[
+                ("artist_name", artist),
+                ("track_name", title),
+                ("album_name", album),
+                ("duration", &duration),
+            ])
+            .send()
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let track: LrclibTrack = res.error_for_status()?.json().await?;
+
+        let lyrics = Lyrics {
+            source: LyricsSource::Lrclib,
+            synced: track.synced_lyrics.filter(|l| !l.trim().is_empty()),
+            plain: track.plain_lyrics.filter(|l| !l.trim().is_empty()),
+        };
+        // Instrumentals come back with neither set.
+        Ok((lyrics.synced.is_some() || lyrics.plain.is_some()).then_some(lyrics))
+    }
+}
@@ -1,2 +1,5 @@
 pub mod discord;
+pub mod history;
 pub mod lastfm;
+pub mod lrclib;
+pub mod palette;
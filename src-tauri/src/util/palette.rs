@@ -0,0 +1,78 @@
+use sha2::{Digest, Sha256};
+
+/// Longest edge images are downsampled to before quantizing. Plenty for a few
+/// dominant colors and keeps large covers cheap.
+const SAMPLE_SIZE: u32 = 64;
+
+pub fn cover_hash(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Extracts up to `count` dominant colors from an encoded image using median cut,
+/// most common first, as `#rrggbb` strings.
+pub fn extract_palette(data: &[u8], count: usize) -> Result<Vec<String>, String> {
+    let img = image::load_from_memory(data)
+        .map_err(|e| format!("Failed to decode cover: {}", e))?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgb8();
+    let pixels: Vec<[u8; 3]> = img.pixels().map(|p| p.0).collect();
+    if pixels.is_empty() || count == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut boxes = vec![pixels];
+    while boxes.len() < count {
+        let Some((idx, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = widest_channel(b);
+                (i, channel, range)
+            })
+            .filter(|(_, _, range)| *range > 0)
+            .max_by_key(|(_, _, range)| *range)
+            .map(|(i, channel, _)| (i, channel))
+        else {
+            break;
+        };
+
+        let mut b = boxes.swap_remove(idx);
+        b.sort_unstable_by_key(|p| p[channel]);
+        let upper = b.split_off(b.len() / 2);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+
+    boxes.sort_by_key(|b| std::cmp::Reverse(b.len()));
+    Ok(boxes.iter().map(|b| to_hex(average(b))).collect())
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let (min, max) = pixels.iter().fold((u8::MAX, u8::MIN), |(lo, hi), p| {
+                (lo.min(p[c]), hi.max(p[c]))
+            });
+            (c, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+fn average(pixels: &[[u8; 3]]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    for p in pixels {
+        for (total, channel) in sum.iter_mut().zip(p) {
+            *total += *channel as u64;
+        }
+    }
+    let n = pixels.len().max(1) as u64;
+    [(sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8]
+}
+
+fn to_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}